@@ -0,0 +1,25 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get(name: &str) -> Option<String> {
+    let flag = "--".to_string() + name;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&(flag.clone() + "=")) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn get(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::replay::Playback;
+use crate::save;
+use crate::sim::Kitchen;
+use crate::{Hand, HandAction, ObjectiveZone};
+
+const KEYS: [KeyCode; 56] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::Space,
+    KeyCode::Return,
+    KeyCode::Back,
+    KeyCode::Tab,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::LShift,
+    KeyCode::LControl,
+];
+
+const BUTTONS: [GamepadButtonType; 16] = [
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Shelf1,
+    Shelf2,
+    Shelf3,
+    Feed,
+    Cook,
+    Cancel,
+    NextCustomer,
+}
+
+impl Action {
+    fn shelf(&self) -> Option<usize> {
+        match self {
+            Action::Shelf1 => Some(2),
+            Action::Shelf2 => Some(1),
+            Action::Shelf3 => Some(0),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Controls {
+    pub keyboard: BTreeMap<Action, Vec<String>>,
+    pub gamepad: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Controls {
+            keyboard: BTreeMap::from([
+                (Action::Shelf1, names(&["Key1", "Numpad1"])),
+                (Action::Shelf2, names(&["Key2", "Numpad2"])),
+                (Action::Shelf3, names(&["Key3", "Numpad3"])),
+                (Action::Feed, names(&["F", "Up"])),
+                (Action::Cook, names(&["C", "Down"])),
+                (Action::Cancel, names(&["X", "Back"])),
+                (Action::NextCustomer, names(&["Tab"])),
+            ]),
+            gamepad: BTreeMap::from([
+                (Action::Shelf1, names(&["DPadUp"])),
+                (Action::Shelf2, names(&["DPadLeft", "DPadRight"])),
+                (Action::Shelf3, names(&["DPadDown"])),
+                (Action::Feed, names(&["South"])),
+                (Action::Cook, names(&["West"])),
+                (Action::Cancel, names(&["East"])),
+                (Action::NextCustomer, names(&["RightTrigger"])),
+            ]),
+        }
+    }
+}
+
+pub struct Bindings {
+    keys: Vec<(KeyCode, Action)>,
+    buttons: Vec<(GamepadButtonType, Action)>,
+}
+
+impl Bindings {
+    pub fn load() -> Bindings {
+        let mut controls: Controls = match save::read("controls") {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                warn!("ignoring the invalid controls: {}", err);
+                Controls::default()
+            }),
+            None => {
+                let controls = Controls::default();
+                if let Err(err) = save::write(
+                    "controls",
+                    &serde_json::to_string_pretty(&controls).unwrap(),
+                ) {
+                    error!("could not save the default controls: {}", err);
+                }
+                controls
+            }
+        };
+        let defaults = Controls::default();
+        for (action, names) in defaults.keyboard {
+            controls.keyboard.entry(action).or_insert(names);
+        }
+        for (action, names) in defaults.gamepad {
+            controls.gamepad.entry(action).or_insert(names);
+        }
+        Bindings::new(&controls)
+    }
+
+    fn new(controls: &Controls) -> Bindings {
+        let mut bindings = Bindings {
+            keys: vec![],
+            buttons: vec![],
+        };
+        for (action, names) in controls.keyboard.iter() {
+            for name in names {
+                match KEYS.iter().find(|key| format!("{:?}", key) == *name) {
+                    Some(key) => bindings.keys.push((*key, *action)),
+                    None => warn!("unknown key `{}` for {:?}", name, action),
+                }
+            }
+        }
+        for (action, names) in controls.gamepad.iter() {
+            for name in names {
+                match BUTTONS
+                    .iter()
+                    .find(|button| format!("{:?}", button) == *name)
+                {
+                    Some(button) => bindings.buttons.push((*button, *action)),
+                    None => warn!("unknown gamepad button `{}` for {:?}", name, action),
+                }
+            }
+        }
+        bindings
+    }
+}
+
+pub fn action_hand(
+    bindings: Res<Bindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    hand: Res<Hand>,
+    kitchen: Res<Kitchen>,
+    playback: Option<Res<Playback>>,
+    mut hand_action: EventWriter<HandAction>,
+) {
+    if playback.is_some() {
+        return;
+    }
+    let mut actions: Vec<Action> = bindings
+        .keys
+        .iter()
+        .filter(|(key, _)| keys.just_pressed(*key))
+        .map(|(_, action)| *action)
+        .collect();
+    for gamepad in gamepads.iter() {
+        actions.extend(
+            bindings
+                .buttons
+                .iter()
+                .filter(|(button, _)| buttons.just_pressed(GamepadButton::new(*gamepad, *button)))
+                .map(|(_, action)| *action),
+        );
+    }
+
+    let mut holding = hand.holding.is_some();
+    for action in actions {
+        if action == Action::NextCustomer {
+            if let Some(customer) = kitchen.next_in_line() {
+                hand_action.send(HandAction::Select { customer });
+            }
+        } else if let Some(shelf) = action.shelf() {
+            if holding {
+                hand_action.send(HandAction::Return);
+            }
+            hand_action.send(HandAction::Take { shelf });
+            holding = true;
+        } else if holding {
+            hand_action.send(match action {
+                Action::Feed => HandAction::Drop {
+                    zone: ObjectiveZone::Stomach,
+                },
+                Action::Cook => HandAction::Drop {
+                    zone: ObjectiveZone::Pan,
+                },
+                _ => HandAction::Return,
+            });
+            holding = false;
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use bevy::asset::{Asset, AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::sim::{DifficultyProfile, DudeInfo, FoodInfo, Interaction, RecipeInfo};
+
+pub const FOOD_CATALOG: &str = "data/base.foods.json";
+pub const DUDE_CATALOG: &str = "data/base.dudes.json";
+pub const RECIPE_CATALOG: &str = "data/base.recipes.json";
+pub const INTERACTION_TABLE: &str = "data/base.interactions.json";
+pub const DIFFICULTY_CATALOG: &str = "data/base.difficulties.json";
+pub const PRESETS: [&str; 4] = ["easy", "normal", "hard", "adaptive"];
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "5d3d5a47-0f6e-4d8c-9a43-7f2a7b0c1e01"]
+#[serde(transparent)]
+pub struct FoodCatalog(pub Vec<FoodInfo>);
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "5d3d5a47-0f6e-4d8c-9a43-7f2a7b0c1e02"]
+#[serde(transparent)]
+pub struct DudeCatalog(pub Vec<DudeInfo>);
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "5d3d5a47-0f6e-4d8c-9a43-7f2a7b0c1e03"]
+#[serde(transparent)]
+pub struct RecipeCatalog(pub Vec<RecipeInfo>);
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "5d3d5a47-0f6e-4d8c-9a43-7f2a7b0c1e04"]
+#[serde(transparent)]
+pub struct InteractionTable(pub Vec<Interaction>);
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "5d3d5a47-0f6e-4d8c-9a43-7f2a7b0c1e05"]
+#[serde(transparent)]
+pub struct DifficultyCatalog(pub Vec<DifficultyProfile>);
+
+pub struct JsonLoader<T> {
+    extensions: [&'static str; 1],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonLoader<T> {
+    pub fn new(extension: &'static str) -> Self {
+        Self {
+            extensions: [extension],
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> AssetLoader for JsonLoader<T>
+where
+    T: DeserializeOwned + Asset,
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let asset: T = parse(load_context.path(), bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+pub fn parse<T: DeserializeOwned>(path: &Path, bytes: &[u8]) -> Result<T, anyhow::Error> {
+    serde_json::from_slice(bytes)
+        .map_err(|err| anyhow::anyhow!("invalid data in {}: {}", path.display(), err))
+}
+
+pub fn missing_sprite(name: &str, food: &[FoodInfo], dude: &[DudeInfo]) -> String {
+    let user = if let Some(food_info) = food.iter().find(|f| f.sprite == name) {
+        format!("food `{}` in {}", food_info.sprite, FOOD_CATALOG)
+    } else if let Some(dude_info) = dude.iter().find(|d| name.starts_with(&d.sprite)) {
+        format!("dude `{}` in {}", dude_info.sprite, DUDE_CATALOG)
+    } else {
+        "the game".to_string()
+    };
+    format!("sprites/{}.png (used by {})", name, user)
+}
+
+pub fn validate(
+    food: &[FoodInfo],
+    dude: &[DudeInfo],
+    recipe: &[RecipeInfo],
+    interaction: &[Interaction],
+    difficulty: &[DifficultyProfile],
+) -> Result<(), String> {
+    if food.is_empty() {
+        return Err(format!("{} has no foods", FOOD_CATALOG));
+    }
+    if dude.is_empty() {
+        return Err(format!("{} has no dudes", DUDE_CATALOG));
+    }
+    for (i, food_info) in food.iter().enumerate() {
+        if food[..i].iter().any(|f| f.sprite == food_info.sprite) {
+            return Err(format!(
+                "{}: food `{}` is listed twice",
+                FOOD_CATALOG, food_info.sprite
+            ));
+        }
+        if let Some((taste, value)) = food_info
+            .flavor
+            .0
+            .iter()
+            .find(|(_, value)| **value < 0.0 || value.fract() != 0.0)
+        {
+            return Err(format!(
+                "{}: food `{}` has {} {}, amounts must be whole and positive",
+                FOOD_CATALOG,
+                food_info.sprite,
+                value,
+                taste.as_str()
+            ));
+        }
+    }
+    for (i, dude_info) in dude.iter().enumerate() {
+        if dude[..i].iter().any(|d| d.name == dude_info.name) {
+            return Err(format!(
+                "{}: dude `{}` is listed twice",
+                DUDE_CATALOG, dude_info.name
+            ));
+        }
+        let odds = &dude_info.palate;
+        if odds.resist + odds.dislike + odds.like + odds.normal == 0 {
+            return Err(format!(
+                "{}: dude `{}` has no palate odds",
+                DUDE_CATALOG, dude_info.name
+            ));
+        }
+        if let Some(taste) = dude_info
+            .likes
+            .iter()
+            .find(|taste| dude_info.dislikes.contains(taste))
+        {
+            return Err(format!(
+                "{}: dude `{}` both likes and dislikes {}",
+                DUDE_CATALOG,
+                dude_info.name,
+                taste.as_str()
+            ));
+        }
+        if dude_info.capacity == 0 {
+            return Err(format!(
+                "{}: dude `{}` has no stomach capacity",
+                DUDE_CATALOG, dude_info.name
+            ));
+        }
+        if dude_info.hidden > 8 {
+            return Err(format!(
+                "{}: dude `{}` hides {} tastes, there are only 8",
+                DUDE_CATALOG, dude_info.name, dude_info.hidden
+            ));
+        }
+        if dude_info.score_multiplier <= 0.0 {
+            return Err(format!(
+                "{}: dude `{}` has a score multiplier of {}, it must be positive",
+                DUDE_CATALOG, dude_info.name, dude_info.score_multiplier
+            ));
+        }
+    }
+    for (i, recipe_info) in recipe.iter().enumerate() {
+        if recipe[..i].iter().any(|r| r.name == recipe_info.name) {
+            return Err(format!(
+                "{}: recipe `{}` is listed twice",
+                RECIPE_CATALOG, recipe_info.name
+            ));
+        }
+        if recipe_info.ingredients.is_empty() {
+            return Err(format!(
+                "{}: recipe `{}` has no ingredients",
+                RECIPE_CATALOG, recipe_info.name
+            ));
+        }
+        if let Some(ingredient) = recipe_info
+            .ingredients
+            .iter()
+            .find(|ingredient| !food.iter().any(|f| f.sprite == **ingredient))
+        {
+            return Err(format!(
+                "{}: recipe `{}` uses `{}` which is not in {}",
+                RECIPE_CATALOG, recipe_info.name, ingredient, FOOD_CATALOG
+            ));
+        }
+    }
+    for interaction in interaction {
+        if interaction.taste == interaction.with {
+            return Err(format!(
+                "{}: {} can't react with itself",
+                INTERACTION_TABLE,
+                interaction.taste.as_str()
+            ));
+        }
+        if !(0.0..=1.0).contains(&interaction.chance) {
+            return Err(format!(
+                "{}: {} {} {} has chance {}, it must be between 0 and 1",
+                INTERACTION_TABLE,
+                interaction.taste.as_str(),
+                interaction.effect.as_str(),
+                interaction.with.as_str(),
+                interaction.chance
+            ));
+        }
+    }
+    for preset in PRESETS {
+        if !difficulty.iter().any(|d| d.name == preset) {
+            return Err(format!(
+                "{} has no `{}` difficulty",
+                DIFFICULTY_CATALOG, preset
+            ));
+        }
+    }
+    for (i, profile) in difficulty.iter().enumerate() {
+        if difficulty[..i].iter().any(|d| d.name == profile.name) {
+            return Err(format!(
+                "{}: difficulty `{}` is listed twice",
+                DIFFICULTY_CATALOG, profile.name
+            ));
+        }
+        if profile.round_secs <= 0.0 || profile.reward_secs <= 0.0 || profile.cook_interval <= 0.0 {
+            return Err(format!(
+                "{}: difficulty `{}` needs positive round, reward and cook times",
+                DIFFICULTY_CATALOG, profile.name
+            ));
+        }
+        if !(0.0..=1.0).contains(&profile.duplication_chance) {
+            return Err(format!(
+                "{}: difficulty `{}` has duplication chance {}, it must be between 0 and 1",
+                DIFFICULTY_CATALOG, profile.name, profile.duplication_chance
+            ));
+        }
+        if let Some(adaptive) = &profile.adaptive {
+            if adaptive.window == 0 || !(0.0..=1.0).contains(&adaptive.target_win_rate) {
+                return Err(format!(
+                    "{}: difficulty `{}` needs an adaptive window and a target win rate between 0 and 1",
+                    DIFFICULTY_CATALOG, profile.name
+                ));
+            }
+            if !(1..=adaptive.window).contains(&adaptive.min_rounds) {
+                return Err(format!(
+                    "{}: difficulty `{}` has min_rounds {}, it must be between 1 and the window",
+                    DIFFICULTY_CATALOG, profile.name, adaptive.min_rounds
+                ));
+            }
+        }
+        if !(1..=3).contains(&profile.shelves) {
+            return Err(format!(
+                "{}: difficulty `{}` has {} shelves, it must have 1 to 3",
+                DIFFICULTY_CATALOG, profile.name, profile.shelves
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn foods() -> Vec<FoodInfo> {
+        parse::<FoodCatalog>(
+            Path::new(FOOD_CATALOG),
+            br#"[{"sprite": "cake", "flavor": {"Sweet": 2}}]"#,
+        )
+        .unwrap()
+        .0
+    }
+
+    fn dudes() -> Vec<DudeInfo> {
+        parse::<DudeCatalog>(
+            Path::new(DUDE_CATALOG),
+            br#"[{"name": "dude", "sprite": "dude"}]"#,
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn unknown_taste() {
+        let err = parse::<FoodCatalog>(
+            Path::new(FOOD_CATALOG),
+            br#"[{"sprite": "cake", "flavor": {"Umami": 2}}]"#,
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(err.starts_with("invalid data in data/base.foods.json: unknown variant `Umami`"));
+    }
+
+    #[test]
+    fn missing_sprites() {
+        assert_eq!(
+            missing_sprite("cake", &foods(), &dudes()),
+            "sprites/cake.png (used by food `cake` in data/base.foods.json)"
+        );
+        assert_eq!(
+            missing_sprite("dude-puke", &foods(), &dudes()),
+            "sprites/dude-puke.png (used by dude `dude` in data/base.dudes.json)"
+        );
+        assert_eq!(
+            missing_sprite("pan", &foods(), &dudes()),
+            "sprites/pan.png (used by the game)"
+        );
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let mut food = foods();
+        food.extend(foods());
+        assert_eq!(
+            validate(&food, &dudes(), &[], &[], &[]),
+            Err("data/base.foods.json: food `cake` is listed twice".to_string())
+        );
+        let mut dude = dudes();
+        dude.extend(dudes());
+        assert_eq!(
+            validate(&foods(), &dude, &[], &[], &[]),
+            Err("data/base.dudes.json: dude `dude` is listed twice".to_string())
+        );
+    }
+}
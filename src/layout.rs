@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+
+use crate::{Food, FoodState, Pan};
+
+pub const RES: Vec2 = Vec2::new(1200.0, 700.0);
+
+pub const TOP: Vec2 = Vec2::new(0.0, 1.0);
+pub const BOTTOM: Vec2 = Vec2::new(0.0, -1.0);
+pub const LEFT: Vec2 = Vec2::new(-1.0, 0.0);
+pub const RIGHT: Vec2 = Vec2::new(1.0, 0.0);
+pub const TOP_LEFT: Vec2 = Vec2::new(-1.0, 1.0);
+pub const TOP_RIGHT: Vec2 = Vec2::new(1.0, 1.0);
+pub const BOTTOM_RIGHT: Vec2 = Vec2::new(1.0, -1.0);
+
+#[derive(Component, Clone, Copy)]
+pub struct Layout {
+    pub anchor: Vec2,
+    pub offset: Vec2,
+}
+
+impl Layout {
+    pub fn new(anchor: Vec2, offset: Vec2) -> Self {
+        Layout { anchor, offset }
+    }
+
+    pub fn at(&self, half: Vec2, z: f32) -> Vec3 {
+        (self.anchor * half + self.offset).extend(z)
+    }
+
+    pub fn shifted(&self, by: Vec2) -> Self {
+        Layout::new(self.anchor, self.offset + by)
+    }
+}
+
+pub fn camera() -> Camera2dBundle {
+    let mut camera_bundle = Camera2dBundle::new_with_far(100.0);
+    camera_bundle.projection.scaling_mode = ScalingMode::Auto {
+        min_width: RES.x,
+        min_height: RES.y,
+    };
+    camera_bundle
+}
+
+pub fn apply_layout(
+    projection_query: Query<&OrthographicProjection>,
+    mut layout_query: Query<(&Layout, &mut Transform, Option<&mut Pan>)>,
+    mut food_query: Query<(&Food, &mut Transform), Without<Layout>>,
+) {
+    let half = match projection_query.get_single() {
+        Ok(projection) => Vec2::new(projection.right, projection.top) * projection.scale,
+        Err(_) => return,
+    };
+    for (layout, mut tr, pan) in &mut layout_query {
+        let translation = layout.at(half, tr.translation.z);
+        if let Some(mut pan) = pan {
+            pan.from = translation;
+            if pan.timer.elapsed_secs() > 0.0 {
+                continue;
+            }
+        }
+        if tr.translation != translation {
+            tr.translation = translation;
+        }
+    }
+    for (food, mut tr) in &mut food_query {
+        if food.state != FoodState::Shelved {
+            continue;
+        }
+        if let Ok((_, shelf_tr, _)) = layout_query.get(food.shelf) {
+            if tr.translation != shelf_tr.translation {
+                tr.translation = shelf_tr.translation;
+            }
+        }
+    }
+}
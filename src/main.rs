@@ -1,14 +1,47 @@
-use bevy::asset::LoadState;
+use bevy::asset::{AssetServerSettings, LoadState};
 use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
 use bevy::prelude::*;
+use bevy::render::camera::CameraUpdateSystem;
+use bevy::transform::TransformSystem;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::ops::Add;
 use std::time::Duration;
 
+mod args;
+mod controls;
+mod data;
+mod layout;
+mod menu;
+mod reload;
+mod replay;
+mod rng;
+mod save;
+mod sim;
+mod tooltip;
+mod touch;
+
+use controls::Bindings;
+use data::{
+    DifficultyCatalog, DudeCatalog, FoodCatalog, InteractionTable, JsonLoader, RecipeCatalog,
+};
+use layout::{Layout, BOTTOM, BOTTOM_RIGHT, LEFT, RIGHT, TOP, TOP_LEFT, TOP_RIGHT};
+use replay::{Playback, Recorder, Replay};
+use rng::Rngs;
+use save::Save;
+use sim::{
+    Customer, DifficultyProfile, DudeInfo, FoodInfo, Interaction, Kitchen, Preference, RecipeInfo,
+    RunRules, Taste, TickEvent, Token,
+};
+
 fn main() {
     let mut app = App::new();
     app.insert_resource(bevy::render::texture::ImageSettings::default_nearest())
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: cfg!(not(target_arch = "wasm32")),
+            ..default()
+        })
         .add_plugins(DefaultPlugins)
         .insert_resource(WindowDescriptor {
             title: "perfect chef".to_string(),
@@ -17,38 +50,117 @@ fn main() {
             ..default()
         })
         .init_resource::<RawHandles>()
+        .init_resource::<reload::PendingReload>()
         .insert_resource(ClearColor(Color::BLACK))
+        .add_asset::<FoodCatalog>()
+        .add_asset::<DudeCatalog>()
+        .add_asset::<RecipeCatalog>()
+        .add_asset::<InteractionTable>()
+        .add_asset::<DifficultyCatalog>()
+        .add_asset_loader(JsonLoader::<FoodCatalog>::new("foods.json"))
+        .add_asset_loader(JsonLoader::<DudeCatalog>::new("dudes.json"))
+        .add_asset_loader(JsonLoader::<RecipeCatalog>::new("recipes.json"))
+        .add_asset_loader(JsonLoader::<InteractionTable>::new("interactions.json"))
+        .add_asset_loader(JsonLoader::<DifficultyCatalog>::new("difficulties.json"))
         .add_state(AppState::Init)
+        .add_system_set(SystemSet::on_enter(AppState::Init).with_system(load_data))
         .add_system_set(SystemSet::on_update(AppState::Init).with_system(init))
         .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(load_all))
         .add_system_set(SystemSet::on_update(AppState::Setup).with_system(check_all))
         .add_system_set(SystemSet::on_exit(AppState::Setup).with_system(setup))
-        .add_system_set(SystemSet::on_enter(AppState::Game).with_system(spawn_dude))
+        .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(menu::main_menu))
+        .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(menu::despawn_menu))
+        .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(menu::pause_menu))
+        .add_system_set(
+            SystemSet::on_update(AppState::Paused)
+                .with_system(menu::menu_buttons.after(GameLabel::Pause))
+                .with_system(menu::pause_key.label(GameLabel::Pause)),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(menu::despawn_menu))
+        .add_system_set(
+            SystemSet::on_enter(AppState::GameOver)
+                .with_system(save::record_best.label(GameLabel::Save))
+                .with_system(menu::game_over_menu.after(GameLabel::Save)),
+        )
+        .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(menu::despawn_menu))
+        .add_system_set(
+            SystemSet::on_enter(AppState::HighScores).with_system(menu::high_scores_menu),
+        )
+        .add_system_set(SystemSet::on_update(AppState::HighScores).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::HighScores).with_system(menu::despawn_menu))
+        .add_system_set(
+            SystemSet::on_enter(AppState::RecipeBook).with_system(menu::recipe_book_menu),
+        )
+        .add_system_set(SystemSet::on_update(AppState::RecipeBook).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::RecipeBook).with_system(menu::despawn_menu))
+        .add_system_set(
+            SystemSet::on_enter(AppState::HowToPlay).with_system(menu::how_to_play_menu),
+        )
+        .add_system_set(SystemSet::on_update(AppState::HowToPlay).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::HowToPlay).with_system(menu::despawn_menu))
+        .add_system_set(SystemSet::on_enter(AppState::ChooseMode).with_system(menu::mode_menu))
+        .add_system_set(SystemSet::on_update(AppState::ChooseMode).with_system(menu::menu_buttons))
+        .add_system_set(SystemSet::on_exit(AppState::ChooseMode).with_system(menu::despawn_menu))
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(reset_run.label(GameLabel::Reset))
+                .with_system(
+                    seat_customers
+                        .label(GameLabel::Seat)
+                        .after(GameLabel::Reset),
+                )
+                .with_system(show_customers.after(GameLabel::Seat))
+                .with_system(resize_ui_timer.after(GameLabel::Reset)),
+        )
         .add_system_set(
             SystemSet::on_update(AppState::Game)
+                .with_system(menu::pause_key.label(GameLabel::Pause))
                 .with_system(move_hand)
-                .with_system(use_hand)
+                .with_system(return_anim)
+                .with_system(mouse_hand.label(GameLabel::Input))
+                .with_system(controls::action_hand.label(GameLabel::Input))
+                .with_system(touch::touch_hand.label(GameLabel::Input))
+                .with_system(replay::play_replay.label(GameLabel::Input))
+                .with_system(
+                    replay::record_actions
+                        .after(GameLabel::Input)
+                        .before(GameLabel::Hand),
+                )
+                .with_system(use_hand.label(GameLabel::Hand).after(GameLabel::Input))
                 .with_system(refresh_shelf)
                 .with_system(restock_shelf)
                 .with_system(eat_anim)
-                .with_system(cooking)
+                .with_system(cooking.label(GameLabel::Cook).after(GameLabel::Hand))
+                .with_system(seat_customers.label(GameLabel::Seat).after(GameLabel::Cook))
+                .with_system(show_customers.after(GameLabel::Seat))
+                .with_system(patience_labels)
+                .with_system(reaction_labels)
                 .with_system(spawn_objectives)
                 .with_system(move_objs)
-                .with_system(match_timers)
+                .with_system(match_timers.after(GameLabel::Cook).after(GameLabel::Pause))
+                .with_system(tooltip::update_tooltip.after(GameLabel::Hand))
                 .with_system(update_ui)
                 .with_system(update_ui_timer),
         )
         .add_system_set(
             SystemSet::on_exit(AppState::Game)
                 .with_system(clear_hand)
-                .with_system(ready_anim_objs),
+                .with_system(ready_anim_objs)
+                .with_system(tooltip::despawn_tooltip)
+                .with_system(touch::clear_drag)
+                .with_system(replay::save_replay),
         )
         .add_system_set(
             SystemSet::on_update(AppState::Reward)
+                .with_system(menu::pause_key.label(GameLabel::Pause))
                 .with_system(pan_anim)
                 .with_system(eat_anim)
+                .with_system(patience_labels)
+                .with_system(reaction_labels)
                 .with_system(move_objs)
-                .with_system(match_timers)
+                .with_system(match_timers.after(GameLabel::Pause))
                 .with_system(update_ui)
                 .with_system(update_ui_timer)
                 .with_system(tally),
@@ -56,20 +168,56 @@ fn main() {
         .add_system_set(
             SystemSet::on_exit(AppState::Reward)
                 .with_system(pan_reset)
-                .with_system(reset_ui),
+                .with_system(next_round)
+                .with_system(reset_ui)
+                .with_system(save::store_stats),
         )
+        .add_system(reload::watch_data)
+        .add_system(reload::apply_reload)
+        .add_system(reload::reload_sprites)
+        .add_system(save::track_stats)
         .add_system_to_stage(CoreStage::PreUpdate, mouse_pos)
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            layout::apply_layout
+                .after(CameraUpdateSystem)
+                .before(TransformSystem::TransformPropagate),
+        )
         .init_resource::<AtlasHandles>()
         .init_resource::<AudioHandles>()
         .init_resource::<Hand>()
         .init_resource::<MousePos>()
-        .init_resource::<Score>()
+        .init_resource::<touch::TouchDrag>()
         .init_resource::<MatchTimers>()
-        .insert_resource(Difficulty { threshold: 30 })
+        .init_resource::<Restart>()
+        .init_resource::<Reseat>()
+        .init_resource::<Mode>()
+        .insert_resource(Save::load())
+        .insert_resource(Bindings::load())
         .add_event::<RefreshShelfEvent>()
         .add_event::<RestockShelfEvent>()
         .add_event::<EatEvent>()
-        .add_event::<PanSmashEvent>();
+        .add_event::<HandAction>()
+        .add_event::<PanSmashEvent>()
+        .add_event::<ServedEvent>();
+
+    let replay = Replay::from_args();
+    let (rngs, rules) = match &replay {
+        Some(replay) => (
+            Rngs::new(replay.seed),
+            RunRules {
+                lives: replay.lives,
+            },
+        ),
+        None => (Rngs::from_args(), run_rules()),
+    };
+    if let Some(replay) = replay {
+        app.insert_resource(Playback::new(replay));
+    } else if let Some(recorder) = Recorder::from_args(rngs.seed, rules.lives) {
+        app.insert_resource(recorder);
+    }
+    app.insert_resource(rngs)
+        .insert_resource(Kitchen::new(DifficultyProfile::default(), rules));
 
     #[cfg(target_arch = "wasm32")]
     {
@@ -79,114 +227,193 @@ fn main() {
     app.run();
 }
 
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum GameLabel {
+    Pause,
+    Reset,
+    Seat,
+    Input,
+    Hand,
+    Cook,
+    Save,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum AppState {
     Init,
     Setup,
+    MainMenu,
     Game,
     Reward,
+    Paused,
+    GameOver,
+    HighScores,
+    RecipeBook,
+    HowToPlay,
+    ChooseMode,
 }
 
-fn init(mut commands: Commands, mut state: ResMut<State<AppState>>) {
-    let info = Info {
-        atlases: vec![],
-        food: vec![
-            FoodInfo {
-                sprite: "leg".to_string(),
-                flavor: Flavor(HashMap::from([
-                    (Taste::Sweet, 1.0),
-                    (Taste::Salty, 1.0),
-                    (Taste::Savory, 2.0),
-                    (Taste::Spicy, 1.0),
-                ])),
-            },
-            FoodInfo {
-                sprite: "chili".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Spicy, 3.0)])),
-            },
-            FoodInfo {
-                sprite: "chocolate".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Bitter, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "ice-cream".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Dry, 1.0), (Taste::Cool, 2.0)])),
-            },
-            FoodInfo {
-                sprite: "fish".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Savory, 1.0), (Taste::Salty, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "coffee".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Dry, 1.0), (Taste::Bitter, 2.0)])),
-            },
-            FoodInfo {
-                sprite: "lemon".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Sour, 3.0)])),
-            },
-            FoodInfo {
-                sprite: "cheese".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Sweet, 1.0), (Taste::Savory, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "cinnamon".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Dry, 1.0), (Taste::Spicy, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "mint".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Cool, 2.0)])),
-            },
-            FoodInfo {
-                sprite: "apple".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Sweet, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "blueberry".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Sweet, 2.0), (Taste::Sour, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "feather".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Dry, 2.0)])),
-            },
-            FoodInfo {
-                sprite: "gas".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Spicy, 2.0), (Taste::Bitter, 2.0)])),
-            },
-            FoodInfo {
-                sprite: "dynamite".to_string(),
-                flavor: Flavor(HashMap::from([(Taste::Spicy, 2.0), (Taste::Savory, 1.0)])),
-            },
-            FoodInfo {
-                sprite: "chips".to_string(),
-                flavor: Flavor(HashMap::from([
-                    (Taste::Sweet, 1.0),
-                    (Taste::Salty, 3.0),
-                    (Taste::Dry, 1.0),
-                ])),
-            },
-            FoodInfo {
-                sprite: "onion".to_string(),
-                flavor: Flavor(HashMap::from([
-                    (Taste::Sweet, 1.0),
-                    (Taste::Sour, 2.0),
-                    (Taste::Spicy, 1.0),
-                ])),
-            },
-        ],
-        dude: vec![
-            DudeInfo {
-                sprite: "dude".to_string(),
-            },
-            DudeInfo {
-                sprite: "ale".to_string(),
-            },
-            DudeInfo {
-                sprite: "elena".to_string(),
-            },
-        ],
+const QUEUE_SIZE: usize = 3;
+const WAITING_SCALE: f32 = 0.4;
+
+fn run_rules() -> RunRules {
+    let lives = match args::get("lives").map(|lives| lives.parse::<u32>()) {
+        Some(Ok(lives)) => lives,
+        Some(Err(err)) => panic!("invalid --lives: {}", err),
+        None => 3,
     };
-    commands.insert_resource(info);
-    state.set(AppState::Setup).unwrap();
+    RunRules { lives }
+}
+
+#[derive(Default)]
+struct Restart(bool);
+
+#[derive(Default)]
+struct Reseat(bool);
+
+struct Mode {
+    difficulty: String,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode {
+            difficulty: "normal".to_string(),
+        }
+    }
+}
+
+fn reset_run(
+    mut commands: Commands,
+    mut restart: ResMut<Restart>,
+    mut reseat: ResMut<Reseat>,
+    mut started: Local<bool>,
+    mode: Res<Mode>,
+    info: Res<Info>,
+    mut kitchen: ResMut<Kitchen>,
+    mut match_timers: ResMut<MatchTimers>,
+    mut rngs: ResMut<Rngs>,
+    mut hand: ResMut<Hand>,
+    recorder: Option<ResMut<Recorder>>,
+    playback: Option<Res<Playback>>,
+    food_query: Query<(Entity, &Food)>,
+    obj_query: Query<(Entity, &Objective)>,
+    mut pan_query: Query<(&mut Pan, &mut Transform, &mut TextureAtlasSprite)>,
+    mut refresh_event: EventWriter<RefreshShelfEvent>,
+) {
+    if !restart.0 {
+        return;
+    }
+    restart.0 = false;
+    let replaying = playback.is_some() && kitchen.ticks == 0;
+    let difficulty = match playback {
+        Some(playback) if replaying => playback.difficulty.clone(),
+        _ => mode.difficulty.clone(),
+    };
+    if kitchen.ticks > 0 {
+        commands.remove_resource::<Playback>();
+    }
+    rngs.reseed(!*started || replaying || args::get("seed").is_some());
+    *started = true;
+    info!(
+        "seed {}, replay this run with --seed {}",
+        rngs.seed, rngs.seed
+    );
+    if let Some(mut recorder) = recorder {
+        recorder.restart(rngs.seed, &difficulty);
+    }
+
+    let profile = info
+        .difficulty
+        .iter()
+        .find(|profile| profile.name == difficulty)
+        .unwrap_or_else(|| panic!("unknown difficulty `{}`", difficulty))
+        .clone();
+    *match_timers = MatchTimers::new(&profile);
+    *kitchen = Kitchen::new(profile, kitchen.rules.clone());
+    hand.holding = None;
+    for (ent, food) in &food_query {
+        if food.state != FoodState::Shelved {
+            commands.entity(ent).despawn();
+        }
+    }
+    for (ent, obj) in &obj_query {
+        if obj.zone == ObjectiveZone::Pan {
+            commands.entity(ent).despawn();
+        }
+    }
+    reseat.0 = true;
+    let (mut pan, mut tr, mut sprite) = pan_query.single_mut();
+    pan.timer.set_duration(match_timers.pan());
+    pan.timer.reset();
+    pan.smashed = false;
+    tr.translation = pan.from;
+    sprite.index = 0;
+    refresh_event.send(RefreshShelfEvent { clear: false });
+}
+
+fn load_data(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DataHandles {
+        food: asset_server.load(data::FOOD_CATALOG),
+        dude: asset_server.load(data::DUDE_CATALOG),
+        recipe: asset_server.load(data::RECIPE_CATALOG),
+        interaction: asset_server.load(data::INTERACTION_TABLE),
+        difficulty: asset_server.load(data::DIFFICULTY_CATALOG),
+    });
+}
+
+fn init(
+    mut commands: Commands,
+    mut state: ResMut<State<AppState>>,
+    asset_server: Res<AssetServer>,
+    data_handles: Res<DataHandles>,
+    food_catalogs: Res<Assets<FoodCatalog>>,
+    dude_catalogs: Res<Assets<DudeCatalog>>,
+    recipe_catalogs: Res<Assets<RecipeCatalog>>,
+    interaction_tables: Res<Assets<InteractionTable>>,
+    difficulty_catalogs: Res<Assets<DifficultyCatalog>>,
+) {
+    for (path, id) in [
+        (data::FOOD_CATALOG, data_handles.food.id),
+        (data::DUDE_CATALOG, data_handles.dude.id),
+        (data::RECIPE_CATALOG, data_handles.recipe.id),
+        (data::INTERACTION_TABLE, data_handles.interaction.id),
+        (data::DIFFICULTY_CATALOG, data_handles.difficulty.id),
+    ] {
+        if let LoadState::Failed = asset_server.get_load_state(id) {
+            panic!("could not load {}, see the error above", path);
+        }
+    }
+    if let (Some(food), Some(dude), Some(recipe), Some(interaction), Some(difficulty)) = (
+        food_catalogs.get(&data_handles.food),
+        dude_catalogs.get(&data_handles.dude),
+        recipe_catalogs.get(&data_handles.recipe),
+        interaction_tables.get(&data_handles.interaction),
+        difficulty_catalogs.get(&data_handles.difficulty),
+    ) {
+        if let Err(err) = data::validate(&food.0, &dude.0, &recipe.0, &interaction.0, &difficulty.0)
+        {
+            panic!("{}", err);
+        }
+        let info = Info {
+            atlases: vec![],
+            food: food.0.clone(),
+            dude: dude.0.clone(),
+            recipe: recipe.0.clone(),
+            interaction: interaction.0.clone(),
+            difficulty: difficulty.0.clone(),
+        };
+        commands.insert_resource(info);
+        state.set(AppState::Setup).unwrap();
+    }
+}
+
+struct DataHandles {
+    food: Handle<FoodCatalog>,
+    dude: Handle<DudeCatalog>,
+    recipe: Handle<RecipeCatalog>,
+    interaction: Handle<InteractionTable>,
+    difficulty: Handle<DifficultyCatalog>,
 }
 
 #[derive(Default)]
@@ -212,10 +439,18 @@ fn load_all(
     mut audio_handles: ResMut<AudioHandles>,
     mut info: ResMut<Info>,
 ) {
+    let mut dude_sprites: Vec<[String; 3]> = vec![];
+    for dude_info in info.dude.iter() {
+        let sprites = [
+            dude_info.sprite.clone(),
+            dude_info.sprite.clone() + "-gnam",
+            dude_info.sprite.clone() + "-puke",
+        ];
+        if !dude_sprites.contains(&sprites) {
+            dude_sprites.push(sprites);
+        }
+    }
     let mut atlases = vec![
-        vec!["dude", "dude-gnam", "dude-puke"],
-        vec!["elena", "elena-gnam", "elena-puke"],
-        vec!["ale", "ale-gnam", "ale-puke"],
         vec!["pan", "pan-anim1", "pan-anim2"],
         vec!["Time"],
         vec!["guuut"],
@@ -226,6 +461,9 @@ fn load_all(
         vec!["pan-icon"],
         vec!["background"],
     ];
+    for sprites in dude_sprites.iter() {
+        atlases.push(sprites.iter().map(|name| name.as_str()).collect());
+    }
     let food = info.food.clone();
     for food_info in food.iter() {
         atlases.push(vec![&food_info.sprite]);
@@ -246,22 +484,16 @@ fn load_all(
         }
         info.atlases.push(v);
     }
-    let audio_names = vec![
-        "lol",
-        "elena-gnam",
-        "elena-puke",
-        "elena-yeah",
-        "dude-gnam",
-        "dude-puke",
-        "dude-yeah",
-        "ale-gnam",
-        "ale-puke",
-        "ale-yeah",
-    ];
+    let mut audio_names = vec!["lol".to_string()];
+    for sprites in dude_sprites.iter() {
+        for sound in ["-gnam", "-puke", "-yeah"] {
+            audio_names.push(sprites[0].clone() + sound);
+        }
+    }
     for name in audio_names {
-        let handle = asset_server.load(&("audio/".to_string() + name + ".ogg"));
+        let handle = asset_server.load(&("audio/".to_string() + &name + ".ogg"));
         raw_handles.audio.push(handle.clone());
-        audio_handles.handles.insert(name.to_string(), handle);
+        audio_handles.handles.insert(name, handle);
     }
 }
 
@@ -269,18 +501,54 @@ fn check_all(
     mut state: ResMut<State<AppState>>,
     handles: ResMut<RawHandles>,
     asset_server: Res<AssetServer>,
+    info: Res<Info>,
 ) {
+    let mut missing: Vec<String> = handles
+        .sprite_map
+        .iter()
+        .filter(|(_, handle)| matches!(asset_server.get_load_state(*handle), LoadState::Failed))
+        .map(|(name, _)| data::missing_sprite(name, &info.food, &info.dude))
+        .collect();
+    missing.extend(
+        handles
+            .audio
+            .iter()
+            .filter(|handle| matches!(asset_server.get_load_state(*handle), LoadState::Failed))
+            .filter_map(|handle| asset_server.get_handle_path(handle))
+            .map(|path| format!("{}", path.path().display())),
+    );
+    if !missing.is_empty() {
+        missing.sort();
+        panic!("missing assets:\n  {}", missing.join("\n  "));
+    }
     if let LoadState::Loaded =
         asset_server.get_group_load_state(handles.sprites.iter().map(|handle| handle.id))
     {
         if let LoadState::Loaded =
             asset_server.get_group_load_state(handles.audio.iter().map(|handle| handle.id))
         {
-            state.set(AppState::Game).unwrap();
+            state.set(AppState::MainMenu).unwrap();
         }
     }
 }
 
+fn build_atlas(
+    names: &[String],
+    asset_server: &AssetServer,
+    textures: &mut Assets<Image>,
+) -> TextureAtlas {
+    let mut texture_atlas_builder = TextureAtlasBuilder::default();
+
+    for name in names {
+        let handle = asset_server.get_handle("sprites/".to_string() + name + ".png");
+        let texture = textures
+            .get(&handle)
+            .expect(&("no texture ".to_string() + name));
+        texture_atlas_builder.add_texture(handle, texture);
+    }
+    texture_atlas_builder.finish(textures).unwrap()
+}
+
 fn setup(
     mut commands: Commands,
     mut atlas_handles: ResMut<AtlasHandles>,
@@ -294,24 +562,11 @@ fn setup(
     audio: Res<Audio>,
     assets: Res<Assets<TextureAtlas>>,
 ) {
-    let res = Vec2::new(1200.0, 700.0);
-    let halfres = res / 2.0;
-
-    let camera_bundle = Camera2dBundle::new_with_far(100.0);
-    commands.spawn_bundle(camera_bundle);
+    commands.spawn_bundle(layout::camera());
 
     for atlas in info.atlases.iter() {
-        let mut texture_atlas_builder = TextureAtlasBuilder::default();
-
-        for name in atlas {
-            let handle = asset_server.get_handle("sprites/".to_string() + name + ".png");
-            let texture = textures
-                .get(&handle)
-                .expect(&("no texture ".to_string() + name));
-            texture_atlas_builder.add_texture(handle, texture);
-        }
-        let texture_atlas = texture_atlas_builder.finish(&mut textures).unwrap();
-        let atlas_handle = texture_atlases.add(texture_atlas.clone());
+        let texture_atlas = build_atlas(atlas, &asset_server, &mut textures);
+        let atlas_handle = texture_atlases.add(texture_atlas);
         atlas_handles
             .handles
             .insert(atlas[0].to_string(), atlas_handle);
@@ -331,12 +586,13 @@ fn setup(
             sprite: TextureAtlasSprite::new(0),
             texture_atlas: atlas_handles.handles.get("guuut").unwrap().clone(),
             transform: Transform {
-                translation: Vec3::new(150.0, halfres.y - 256.0 + 64.0, 1.0),
+                translation: Vec3::new(0.0, 0.0, 1.0),
                 scale: Vec3::splat(0.5),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(TOP, Vec2::new(150.0, -192.0)))
         .insert(DudePreferencePoint {
             preference: Preference::Like,
         });
@@ -345,12 +601,13 @@ fn setup(
             sprite: TextureAtlasSprite::new(0),
             texture_atlas: atlas_handles.handles.get("bad").unwrap().clone(),
             transform: Transform {
-                translation: Vec3::new(150.0, halfres.y - 256.0, 1.0),
+                translation: Vec3::new(0.0, 0.0, 1.0),
                 scale: Vec3::splat(0.5),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(TOP, Vec2::new(150.0, -256.0)))
         .insert(DudePreferencePoint {
             preference: Preference::Dislike,
         });
@@ -359,12 +616,13 @@ fn setup(
             sprite: TextureAtlasSprite::new(0),
             texture_atlas: atlas_handles.handles.get("resist").unwrap().clone(),
             transform: Transform {
-                translation: Vec3::new(150.0, halfres.y - 256.0 - 64.0, 1.0),
+                translation: Vec3::new(0.0, 0.0, 1.0),
                 scale: Vec3::splat(0.5),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(TOP, Vec2::new(150.0, -320.0)))
         .insert(DudePreferencePoint {
             preference: Preference::Resist,
         });
@@ -372,48 +630,45 @@ fn setup(
     commands
         .spawn()
         .insert(Transform {
-            translation: Vec3::new(0.0, halfres.y - 256.0, 0.1),
+            translation: Vec3::new(0.0, 0.0, 0.1),
             ..default()
         })
+        .insert(Layout::new(TOP, Vec2::new(0.0, -256.0)))
         .insert(DudePoint);
 
     commands
         .spawn()
         .insert(Transform {
-            translation: Vec3::new(0.0, halfres.y - 192.0, 1.0),
+            translation: Vec3::new(0.0, 0.0, 1.0),
             ..default()
         })
+        .insert(Layout::new(TOP, Vec2::new(0.0, -192.0)))
         .insert(MouthPoint);
 
+    commands
+        .spawn()
+        .insert(Transform {
+            translation: Vec3::new(0.0, 0.0, 0.0),
+            ..default()
+        })
+        .insert(ObjectivePoint {
+            zone: ObjectiveZone::Stomach,
+        });
+
     for i in -1..2 {
         commands
             .spawn()
             .insert(Transform {
-                translation: Vec3::new(-halfres.x + 128.0, 200.0 * i as f32, 3.0),
+                translation: Vec3::new(0.0, 0.0, 3.0),
                 ..default()
             })
-            .insert(Shelf);
+            .insert(Layout::new(LEFT, Vec2::new(128.0, 200.0 * i as f32)))
+            .insert(Shelf {
+                index: (i + 1) as usize,
+            });
     }
 
-    let atlas_handle = atlas_handles.handles.get("Time").unwrap();
-    for i in 0..10 {
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                sprite: TextureAtlasSprite::new(0),
-                texture_atlas: atlas_handle.clone(),
-                transform: Transform {
-                    translation: Vec3::new(
-                        500.0 / 2.0 - 500.0 / 20.0 - 500.0 * i as f32 / 10.0,
-                        halfres.y - 48.0,
-                        2.9 - i as f32 / 20.0,
-                    ),
-                    scale: Vec3::splat(0.5),
-                    ..default()
-                },
-                ..default()
-            })
-            .insert(UiTimer { num: i });
-    }
+    spawn_ui_timer(&mut commands, &atlas_handles, &MatchTimers::default());
 
     let atlas_handle = atlas_handles.handles.get("pan").unwrap();
     let atlas = assets.get(atlas_handle).unwrap();
@@ -426,19 +681,19 @@ fn setup(
             ),
             texture_atlas: atlas_handle.clone(),
             transform: Transform {
-                translation: Vec3::new(0.0, -halfres.y + 128.0, 2.0),
+                translation: Vec3::new(0.0, 0.0, 2.0),
                 scale: Vec3::new(1.0, 1.0, 1.0),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(BOTTOM, Vec2::new(0.0, 128.0)))
         .insert(ObjectivePoint {
             zone: ObjectiveZone::Pan,
         })
         .insert(Pan {
-            timer: Timer::new(Duration::from_millis(2000), false),
-            from: Vec3::new(0.0, -halfres.y + 128.0, 2.0),
-            goto: Vec3::new(0.0, halfres.y - 192.0, 2.0),
+            timer: Timer::new(MatchTimers::default().pan(), false),
+            from: Vec3::new(0.0, 0.0, 2.0),
             smashed: false,
         });
 
@@ -448,84 +703,156 @@ fn setup(
         font_size: 72.0,
         color: Color::BLACK,
     };
-    commands.spawn_bundle(SpriteSheetBundle {
-        sprite: TextureAtlasSprite::new(0),
-        texture_atlas: atlas_handles.handles.get("goal").unwrap().clone(),
-        transform: Transform {
-            translation: Vec3::new(halfres.x - 64.0, halfres.y - 128.0, 1.0),
-            scale: Vec3::splat(0.5),
+    commands
+        .spawn_bundle(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(0),
+            texture_atlas: atlas_handles.handles.get("goal").unwrap().clone(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                scale: Vec3::splat(0.5),
+                ..default()
+            },
             ..default()
-        },
-        ..default()
-    });
+        })
+        .insert(Layout::new(TOP_RIGHT, Vec2::new(-64.0, -128.0)));
     commands
         .spawn_bundle(Text2dBundle {
             text: Text::from_section("0", style.clone()).with_alignment(TextAlignment::CENTER),
             transform: Transform {
-                translation: Vec3::new(halfres.x - 64.0, halfres.y - 128.0, 7.0),
+                translation: Vec3::new(0.0, 0.0, 7.0),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(TOP_RIGHT, Vec2::new(-64.0, -128.0)))
         .insert(UiTag {
             name: UiName::Palate,
         });
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_sections([
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 48.0,
+                        color: Color::BLACK,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 32.0,
+                        color: Color::BLACK,
+                    },
+                ),
+            ])
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 7.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Layout::new(TOP_RIGHT, Vec2::new(-150.0, -232.0)))
+        .insert(UiTag {
+            name: UiName::Projection,
+        });
 
-    commands.spawn_bundle(SpriteSheetBundle {
-        sprite: TextureAtlasSprite::new(0),
-        texture_atlas: atlas_handles.handles.get("stomach").unwrap().clone(),
-        transform: Transform {
-            translation: Vec3::new(-20.0 + halfres.x - 64.0, 0.0, 0.1),
-            scale: Vec3::splat(0.5),
+    commands
+        .spawn_bundle(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(0),
+            texture_atlas: atlas_handles.handles.get("stomach").unwrap().clone(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 0.1),
+                scale: Vec3::splat(0.5),
+                ..default()
+            },
             ..default()
-        },
-        ..default()
-    });
+        })
+        .insert(Layout::new(RIGHT, Vec2::new(-84.0, 0.0)));
     commands
         .spawn_bundle(Text2dBundle {
             text: Text::from_section("0", style.clone()).with_alignment(TextAlignment::CENTER),
             transform: Transform {
-                translation: Vec3::new(halfres.x - 64.0, 0.0, 7.0),
+                translation: Vec3::new(0.0, 0.0, 7.0),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(RIGHT, Vec2::new(-64.0, 0.0)))
         .insert(UiTag {
             name: UiName::Stomach,
         });
 
-    commands.spawn_bundle(SpriteSheetBundle {
-        sprite: TextureAtlasSprite::new(0),
-        texture_atlas: atlas_handles.handles.get("pan-icon").unwrap().clone(),
-        transform: Transform {
-            translation: Vec3::new(halfres.x - 64.0, -halfres.y + 128.0, 1.0),
-            scale: Vec3::splat(0.5),
+    commands
+        .spawn_bundle(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(0),
+            texture_atlas: atlas_handles.handles.get("pan-icon").unwrap().clone(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                scale: Vec3::splat(0.5),
+                ..default()
+            },
             ..default()
-        },
-        ..default()
-    });
+        })
+        .insert(Layout::new(BOTTOM_RIGHT, Vec2::new(-64.0, 128.0)));
     commands
         .spawn_bundle(Text2dBundle {
             text: Text::from_section("0", style.clone()).with_alignment(TextAlignment::CENTER),
             transform: Transform {
-                translation: Vec3::new(halfres.x - 64.0, -halfres.y + 128.0, 7.0),
+                translation: Vec3::new(0.0, 0.0, 7.0),
                 ..default()
             },
             ..default()
         })
+        .insert(Layout::new(BOTTOM_RIGHT, Vec2::new(-64.0, 128.0)))
         .insert(UiTag { name: UiName::Pan });
 
     commands
         .spawn_bundle(Text2dBundle {
             text: Text::from_section(
-                "OH YEAH!",
+                "",
                 TextStyle {
                     font: font.clone(),
-                    font_size: 144.0,
-                    color: Color::DARK_GREEN,
+                    font_size: 48.0,
+                    color: Color::BLACK,
                 },
             )
             .with_alignment(TextAlignment::CENTER),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 7.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Layout::new(TOP_LEFT, Vec2::new(128.0, -48.0)))
+        .insert(UiTag {
+            name: UiName::Lives,
+        });
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_sections([
+                TextSection::new(
+                    "OH YEAH!",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 144.0,
+                        color: Color::DARK_GREEN,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 72.0,
+                        color: Color::DARK_GREEN,
+                    },
+                ),
+            ])
+            .with_alignment(TextAlignment::CENTER),
             transform: Transform {
                 translation: Vec3::new(0.0, 0.0, 10.0),
                 ..default()
@@ -535,25 +862,56 @@ fn setup(
         })
         .insert(UiTag { name: UiName::Win });
 
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_sections([
+                TextSection::new(
+                    "DISGUSTING",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 144.0,
+                        color: Color::RED,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 72.0,
+                        color: Color::RED,
+                    },
+                ),
+            ])
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 10.0),
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(UiTag { name: UiName::Lose });
+
     commands
         .spawn_bundle(Text2dBundle {
             text: Text::from_section(
-                "DISGUSTING",
+                "",
                 TextStyle {
                     font: font.clone(),
-                    font_size: 144.0,
-                    color: Color::RED,
+                    font_size: 72.0,
+                    color: Color::ORANGE,
                 },
             )
             .with_alignment(TextAlignment::CENTER),
             transform: Transform {
-                translation: Vec3::new(0.0, 0.0, 10.0),
+                translation: Vec3::new(0.0, -120.0, 10.0),
                 ..default()
             },
-            visibility: Visibility { is_visible: false },
             ..default()
         })
-        .insert(UiTag { name: UiName::Lose });
+        .insert(UiTag {
+            name: UiName::Recipe,
+        });
 
     refresh_event.send(RefreshShelfEvent { clear: true });
 
@@ -567,77 +925,238 @@ fn setup(
     );
 }
 
-fn spawn_dude(
+fn seat_customers(mut kitchen: ResMut<Kitchen>, info: Res<Info>, mut rngs: ResMut<Rngs>) {
+    let rngs = &mut *rngs;
+    while kitchen.customers.len() < QUEUE_SIZE {
+        let dude_info = info.dude[rngs.customers.gen_range(0..info.dude.len())].clone();
+        let patience = rngs.customers.gen_range(25..45) as f32;
+        let palate = dude_info.gen_palate(&mut rngs.palate);
+        let hidden = dude_info.gen_hidden(&mut rngs.palate);
+        kitchen.arrive(dude_info, palate, hidden, patience);
+    }
+}
+
+fn show_customers(
     mut commands: Commands,
-    atlas_handles: ResMut<AtlasHandles>,
-    info: Res<Info>,
-    dude_point: Query<(&DudePoint, &Transform)>,
-    pref_point: Query<(&DudePreferencePoint, &Transform)>,
+    kitchen: Res<Kitchen>,
+    mut reseat: ResMut<Reseat>,
+    atlas_handles: Res<AtlasHandles>,
+    asset_server: Res<AssetServer>,
+    mut rngs: ResMut<Rngs>,
+    dude_point: Query<(&Layout, &Transform), With<DudePoint>>,
+    pref_point: Query<(&DudePreferencePoint, &Layout, &Transform)>,
+    obj_points: Query<(&ObjectivePoint, &Transform)>,
     dude_query: Query<(Entity, &Dude)>,
+    waiting_query: Query<(Entity, &Waiting)>,
     token_query: Query<(Entity, &PreferenceToken)>,
+    obj_query: Query<(Entity, &Objective)>,
 ) {
-    let mut rng = thread_rng();
-    for (ent, _) in &dude_query {
-        commands.entity(ent).despawn();
+    let selected = match kitchen.customer() {
+        Some(customer) => customer,
+        None => return,
+    };
+    let reseat = std::mem::take(&mut reseat.0);
+    let (dude_layout, dude_tr) = dude_point.single();
+    let font = asset_server.load("fonts/SztyletBd.ttf");
+
+    let waiting: Vec<&Customer> = kitchen
+        .customers
+        .iter()
+        .filter(|customer| customer.id != selected.id)
+        .collect();
+    let mut shown: Vec<u32> = waiting_query.iter().map(|(_, w)| w.customer).collect();
+    shown.sort();
+    let mut expected: Vec<u32> = waiting.iter().map(|customer| customer.id).collect();
+    expected.sort();
+    if reseat || shown != expected {
+        for (ent, _) in &waiting_query {
+            commands.entity(ent).despawn_recursive();
+        }
+        for (i, customer) in waiting.iter().enumerate() {
+            let layout = dude_layout.shifted(Vec2::new(-200.0 - 140.0 * i as f32, 64.0));
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite::new(0),
+                    texture_atlas: atlas_handles
+                        .handles
+                        .get(&customer.info.sprite)
+                        .unwrap()
+                        .clone(),
+                    transform: Transform {
+                        translation: dude_tr.translation,
+                        scale: Vec3::splat(WAITING_SCALE),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(layout)
+                .insert(Waiting {
+                    customer: customer.id,
+                })
+                .with_children(|parent| {
+                    spawn_patience_label(parent, customer, font.clone(), 40.0 / WAITING_SCALE);
+                    let mut place = 0.0;
+                    for preference in [Preference::Like, Preference::Dislike] {
+                        for i in 0..8 {
+                            let taste = Taste::from_u32(i);
+                            if customer.hidden.contains(&taste)
+                                || customer.palate.preference(&taste) != preference
+                            {
+                                continue;
+                            }
+                            let color = if preference == Preference::Dislike {
+                                Color::RED
+                            } else {
+                                Color::WHITE
+                            };
+                            parent.spawn_bundle(SpriteSheetBundle {
+                                sprite: TextureAtlasSprite {
+                                    color,
+                                    ..TextureAtlasSprite::new(0)
+                                },
+                                texture_atlas: atlas_handles
+                                    .handles
+                                    .get(taste.as_str())
+                                    .unwrap()
+                                    .clone(),
+                                transform: Transform::from_xyz(-150.0 + place * 100.0, -260.0, 0.1),
+                                ..default()
+                            });
+                            place += 1.0;
+                        }
+                    }
+                });
+        }
+    }
+
+    let respawn = reseat
+        || !dude_query
+            .iter()
+            .any(|(_, dude)| dude.customer == selected.id);
+    let hidden_shown = token_query.iter().filter(|(_, token)| token.hidden).count();
+    if !respawn && hidden_shown == selected.hidden.len() {
+        return;
     }
     for (ent, _) in &token_query {
         commands.entity(ent).despawn();
     }
-    let (_, tr) = dude_point.single();
 
-    let palate = Flavor::gen();
+    if respawn {
+        for (ent, _) in &dude_query {
+            commands.entity(ent).despawn_recursive();
+        }
+        for (ent, obj) in &obj_query {
+            if obj.zone == ObjectiveZone::Stomach {
+                commands.entity(ent).despawn();
+            }
+        }
 
-    let info = &info.dude[rng.gen_range(0..info.dude.len())];
-    let atlas_handle = atlas_handles.handles.get(&info.sprite).unwrap();
-    commands
-        .spawn_bundle(SpriteSheetBundle {
-            sprite: TextureAtlasSprite::new(0),
-            texture_atlas: atlas_handle.clone(),
-            transform: Transform {
-                translation: tr.translation,
-                scale: Vec3::new(1.0, 1.0, 1.0),
+        let atlas_handle = atlas_handles.handles.get(&selected.info.sprite).unwrap();
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(0),
+                texture_atlas: atlas_handle.clone(),
+                transform: Transform {
+                    translation: dude_tr.translation,
+                    scale: Vec3::new(1.0, 1.0, 1.0),
+                    ..default()
+                },
                 ..default()
-            },
-            ..default()
-        })
-        .insert(Dude {
-            puking: false,
-            cycles: 0,
-            timer: Timer::new(Duration::from_millis(rng.gen_range(80..150)), false),
-            palate: palate.clone(),
-            info: info.clone(),
-            yeah: false,
-        });
+            })
+            .insert(Dude {
+                customer: selected.id,
+                puking: false,
+                cycles: 0,
+                timer: Timer::new(Duration::from_millis(rngs.visual.gen_range(80..150)), false),
+                info: selected.info.clone(),
+                yeah: false,
+            })
+            .insert(*dude_layout)
+            .with_children(|parent| {
+                spawn_patience_label(parent, selected, font.clone(), 40.0);
+            });
 
-    commands
-        .spawn()
-        .insert(Transform {
-            translation: Vec3::new(0.0, 0.0, 0.0),
-            ..default()
-        })
-        .insert(ObjectivePoint {
-            zone: ObjectiveZone::Stomach,
-        });
+        let (_, objtr) = obj_points
+            .iter()
+            .find(|(pt, _)| pt.zone == ObjectiveZone::Stomach)
+            .unwrap();
+        let rng = &mut rngs.visual;
+        for token in selected.stomach.iter() {
+            let goto = objtr.translation
+                + Vec3::new(
+                    rng.gen_range(-20..20) as f32,
+                    rng.gen_range(-10..10) as f32,
+                    rng.gen_range(0..1000) as f32 / 10000.0,
+                );
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite::new(0),
+                    texture_atlas: atlas_handles
+                        .handles
+                        .get(token.taste.as_str())
+                        .unwrap()
+                        .clone(),
+                    transform: Transform {
+                        translation: dude_tr.translation,
+                        scale: Vec3::new(0.5, 0.5, 0.5),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(Objective {
+                    id: token.id,
+                    from: dude_tr.translation,
+                    goto,
+                    zone: ObjectiveZone::Stomach,
+                    timer: Timer::new(Duration::from_millis(500), false),
+                });
+        }
+    }
 
     let mut places = Vec::<Preference>::new();
+    let mut hidden = 0;
     for i in 0..8 {
         let taste = Taste::from_u32(i);
         let atlas_handle = atlas_handles.handles.get(taste.as_str()).unwrap();
 
-        let pref: f32 = *palate.0.get(&taste).unwrap_or(&0.0);
-        let preference = Preference::from_f32(pref);
+        if selected.hidden.contains(&taste) {
+            let (_, layout, tr) = pref_point
+                .iter()
+                .find(|(pt, _, _)| pt.preference == Preference::Like)
+                .unwrap();
+            let shift = Vec2::new(hidden as f32 * 48.0 + 64.0, -192.0);
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite {
+                        color: Color::DARK_GRAY,
+                        ..TextureAtlasSprite::new(0)
+                    },
+                    texture_atlas: atlas_handle.clone(),
+                    transform: Transform {
+                        translation: tr.translation + shift.extend(0.0),
+                        scale: Vec3::splat(0.5),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(layout.shifted(shift))
+                .insert(PreferenceToken { hidden: true });
+            hidden += 1;
+            continue;
+        }
 
-        if let Some((_, tr)) = pref_point
+        let preference = selected.palate.preference(&taste);
+
+        if let Some((_, layout, tr)) = pref_point
             .iter()
-            .find(|(pt, _)| pt.preference == preference)
+            .find(|(pt, _, _)| pt.preference == preference)
             .take()
         {
-            let pos = tr.translation
-                + Vec3::new(
-                    places.iter().filter(|p| **p == preference).count() as f32 * 48.0 + 64.0,
-                    0.0,
-                    0.0,
-                );
+            let shift = Vec2::new(
+                places.iter().filter(|p| **p == preference).count() as f32 * 48.0 + 64.0,
+                0.0,
+            );
+            let pos = tr.translation + shift.extend(0.0);
             commands
                 .spawn_bundle(SpriteSheetBundle {
                     sprite: TextureAtlasSprite::new(0),
@@ -649,14 +1168,62 @@ fn spawn_dude(
                     },
                     ..default()
                 })
-                .insert(PreferenceToken {});
+                .insert(layout.shifted(shift))
+                .insert(PreferenceToken { hidden: false });
+        }
+        places.push(preference.clone());
+    }
+}
+
+fn spawn_patience_label(
+    parent: &mut ChildBuilder,
+    customer: &Customer,
+    font: Handle<Font>,
+    font_size: f32,
+) {
+    parent
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size,
+                    color: Color::BLACK,
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, 2.5 * font_size, 0.1),
+            ..default()
+        })
+        .insert(PatienceLabel {
+            customer: customer.id,
+        });
+}
+
+fn patience_labels(kitchen: Res<Kitchen>, mut label_query: Query<(&PatienceLabel, &mut Text)>) {
+    for (label, mut text) in &mut label_query {
+        if let Some(customer) = kitchen
+            .customers
+            .iter()
+            .find(|customer| customer.id == label.customer)
+        {
+            text.sections[0].value = format!(
+                "{} {}s",
+                customer.info.name,
+                customer.patience.ceil() as i32
+            );
+            text.sections[0].style.color = if customer.patience < 5.0 {
+                Color::RED
+            } else {
+                Color::BLACK
+            };
         }
-        places.push(preference.clone());
     }
 }
 
 #[derive(Default)]
 struct MousePos {
+    screen: Option<Vec2>,
     world: Vec2,
 }
 
@@ -666,109 +1233,26 @@ fn mouse_pos(
     mut mousepos: ResMut<MousePos>,
     query_camera: Query<(&Camera, &GlobalTransform)>,
 ) {
-    if let Some((camera, camera_transform)) = query_camera.get_single().ok() {
-        if let Some(window) = windows.get_primary() {
-            for event in cursor_moved_events.iter() {
-                let window_size = Vec2::new(window.width() as f32, window.height() as f32);
-                let ndc = (event.position / window_size) * 2.0 - Vec2::ONE;
-                let ndc_to_world =
-                    camera_transform.compute_matrix() * camera.projection_matrix().inverse();
-                let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-                let world_pos: Vec2 = world_pos.truncate();
-                mousepos.world = world_pos;
-            }
-        }
-    }
-}
-
-#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
-enum Taste {
-    #[default]
-    Sweet,
-    Sour,
-    Salty,
-    Bitter,
-    Savory,
-    Spicy,
-    Cool,
-    Dry,
-}
-
-impl Taste {
-    fn from_u32(value: u32) -> Taste {
-        match value {
-            0 => Taste::Sweet,
-            1 => Taste::Sour,
-            2 => Taste::Salty,
-            3 => Taste::Bitter,
-            4 => Taste::Savory,
-            5 => Taste::Spicy,
-            6 => Taste::Cool,
-            7 => Taste::Dry,
-            _ => panic!("Unknown value: {}", value),
-        }
-    }
-    fn as_str(&self) -> &'static str {
-        match self {
-            Taste::Sweet => "Sweet",
-            Taste::Sour => "Sour",
-            Taste::Salty => "Salty",
-            Taste::Bitter => "Bitter",
-            Taste::Savory => "Savory",
-            Taste::Spicy => "Spicy",
-            Taste::Cool => "Cool",
-            Taste::Dry => "Dry",
-        }
-    }
-}
-
-#[derive(Clone, Default, Debug)]
-struct Flavor(HashMap<Taste, f32>);
-
-impl Add for Flavor {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        let mut sum = Self::default();
-        for i in 0..8 {
-            let taste = Taste::from_u32(i);
-            let value = self.0.get(&taste).unwrap_or(&0.0) + other.0.get(&taste).unwrap_or(&0.0);
-            sum.0.insert(taste, value);
-        }
-        sum
+    if let Some(event) = cursor_moved_events.iter().last() {
+        mousepos.screen = Some(event.position);
     }
-}
-
-impl Flavor {
-    fn gen() -> Self {
-        let mut rng = thread_rng();
-        let mut ret = Self(HashMap::new());
-        for i in 0..8 {
-            let r = rng.gen_range(0..100);
-            let value = if r < 10 {
-                0.0
-            } else if r < 20 {
-                -1.0
-            } else if r < 40 {
-                2.0
-            } else {
-                1.0
-            };
-            ret.0.insert(Taste::from_u32(i), value);
+    if let Some((camera, camera_transform)) = query_camera.get_single().ok() {
+        if let (Some(window), Some(screen)) = (windows.get_primary(), mousepos.screen) {
+            mousepos.world = screen_to_world(screen, window, camera, camera_transform);
         }
-        ret
     }
 }
 
-#[derive(Clone)]
-struct FoodInfo {
-    sprite: String,
-    flavor: Flavor,
-}
-
-#[derive(Clone)]
-struct DudeInfo {
-    sprite: String,
+fn screen_to_world(
+    screen: Vec2,
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Vec2 {
+    let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+    let ndc = (screen / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+    ndc_to_world.project_point3(ndc.extend(-1.0)).truncate()
 }
 
 #[derive(Clone)]
@@ -776,6 +1260,9 @@ struct Info {
     atlases: Vec<Vec<String>>,
     food: Vec<FoodInfo>,
     dude: Vec<DudeInfo>,
+    recipe: Vec<RecipeInfo>,
+    interaction: Vec<Interaction>,
+    difficulty: Vec<DifficultyProfile>,
 }
 
 #[derive(Component)]
@@ -784,16 +1271,15 @@ struct MouthPoint;
 #[derive(Component)]
 struct Pan {
     from: Vec3,
-    goto: Vec3,
     timer: Timer,
     smashed: bool,
 }
 
 #[derive(Component)]
 struct Dude {
+    customer: u32,
     timer: Timer,
     cycles: u32,
-    palate: Flavor,
     puking: bool,
     info: DudeInfo,
     yeah: bool,
@@ -801,7 +1287,7 @@ struct Dude {
 
 #[derive(Component)]
 struct Objective {
-    taste: Taste,
+    id: u32,
     from: Vec3,
     goto: Vec3,
     zone: ObjectiveZone,
@@ -812,6 +1298,7 @@ struct Objective {
 enum FoodState {
     Shelved,
     Held,
+    Returning,
     Eaten,
     Cooking,
 }
@@ -824,7 +1311,7 @@ struct RestockShelfEvent {
     shelf: Entity,
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 enum ObjectiveZone {
     Stomach,
     Pan,
@@ -837,12 +1324,20 @@ struct ObjectivePoint {
 
 struct EatEvent {
     from: Vec3,
-    flavor: Flavor,
+    tokens: Vec<Token>,
+    puke: bool,
+    overate: bool,
     to_zone: ObjectiveZone,
 }
 
 struct PanSmashEvent {}
 
+struct ServedEvent {
+    dude: String,
+    success: bool,
+    recipes: Vec<String>,
+}
+
 #[derive(Component)]
 struct Food {
     state: FoodState,
@@ -850,34 +1345,65 @@ struct Food {
     info: FoodInfo,
 }
 
+#[derive(Component)]
+struct ReturnAnim {
+    from: Vec3,
+    timer: Timer,
+}
+
 #[derive(Default)]
 struct Hand {
     holding: Option<Entity>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum HandAction {
+    Take { shelf: usize },
+    Drop { zone: ObjectiveZone },
+    Return,
+    Select { customer: u32 },
+}
+
+const STEP: f32 = 1.0 / 60.0;
+
 struct MatchTimers {
     game: Timer,
     reward: Timer,
-    cook: Timer,
+    steps: u64,
+    lag: f32,
 }
-impl Default for MatchTimers {
-    fn default() -> Self {
+impl MatchTimers {
+    fn new(profile: &DifficultyProfile) -> Self {
         MatchTimers {
-            game: Timer::new(Duration::from_secs(10), true),
-            reward: Timer::new(Duration::from_secs(5), true),
-            cook: Timer::new(Duration::from_secs(1), true),
+            game: Timer::new(Duration::from_secs_f32(profile.round_secs), true),
+            reward: Timer::new(Duration::from_secs_f32(profile.reward_secs), true),
+            steps: 0,
+            lag: 0.0,
         }
     }
-}
 
-#[derive(Default)]
-struct Score {
-    successes: u32,
-    losses: u32,
-}
+    fn due(&mut self, dt: f32, until: Option<u64>) -> u64 {
+        self.lag += dt;
+        let mut due = (self.lag / STEP) as u64;
+        if let Some(until) = until {
+            due = due.min(until.saturating_sub(self.steps));
+        }
+        self.lag -= due as f32 * STEP;
+        due
+    }
 
-struct Difficulty {
-    threshold: i32,
+    fn pan(&self) -> Duration {
+        Duration::from_millis(2000).min(self.reward.duration() / 2)
+    }
+
+    fn segments(&self) -> u32 {
+        (self.game.duration().as_secs_f32().ceil() as u32).max(1)
+    }
+}
+impl Default for MatchTimers {
+    fn default() -> Self {
+        MatchTimers::new(&DifficultyProfile::default())
+    }
 }
 
 #[derive(PartialEq)]
@@ -885,6 +1411,9 @@ enum UiName {
     Stomach,
     Palate,
     Pan,
+    Projection,
+    Lives,
+    Recipe,
     Win,
     Lose,
 }
@@ -897,31 +1426,93 @@ struct UiTag {
 #[derive(Component)]
 struct UiTimer {
     num: u32,
+    count: u32,
+}
+
+fn spawn_ui_timer(
+    commands: &mut Commands,
+    atlas_handles: &AtlasHandles,
+    match_timers: &MatchTimers,
+) {
+    let atlas_handle = atlas_handles.handles.get("Time").unwrap();
+    let count = match_timers.segments();
+    let width = 500.0 / count as f32;
+    for i in 0..count {
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(0),
+                texture_atlas: atlas_handle.clone(),
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, 2.9 - i as f32 / (2.0 * count as f32)),
+                    scale: Vec3::new(5.0 / count as f32, 0.5, 0.5),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Layout::new(
+                TOP,
+                Vec2::new(500.0 / 2.0 - width / 2.0 - width * i as f32, -48.0),
+            ))
+            .insert(UiTimer { num: i, count });
+    }
+}
+
+fn resize_ui_timer(
+    mut commands: Commands,
+    match_timers: Res<MatchTimers>,
+    atlas_handles: Res<AtlasHandles>,
+    ui_timer_query: Query<(Entity, &UiTimer)>,
+) {
+    let count = match_timers.segments();
+    if ui_timer_query
+        .iter()
+        .all(|(_, uitimer)| uitimer.count == count)
+    {
+        return;
+    }
+    for (ent, _) in &ui_timer_query {
+        commands.entity(ent).despawn();
+    }
+    spawn_ui_timer(&mut commands, &atlas_handles, &match_timers);
 }
 
 fn match_timers(
     mut theme_lol: ResMut<MatchTimers>,
     time: Res<Time>,
     mut state: ResMut<State<AppState>>,
+    kitchen: Res<Kitchen>,
 ) {
     if state.current() == &AppState::Game {
-        theme_lol.game.tick(time.delta());
-        if theme_lol.game.finished() {
-            state.set(AppState::Reward).unwrap();
+        if kitchen.is_over() {
+            state.overwrite_set(AppState::GameOver).unwrap();
+        } else if theme_lol.game.finished() {
+            state.overwrite_set(AppState::Reward).unwrap();
         }
     } else if state.current() == &AppState::Reward {
         theme_lol.reward.tick(time.delta());
         if theme_lol.reward.finished() {
-            state.set(AppState::Game).unwrap();
+            if kitchen.is_over() {
+                state.overwrite_set(AppState::GameOver).unwrap();
+            } else {
+                state.overwrite_set(AppState::Game).unwrap();
+            }
         }
     }
 }
 
-fn reset_ui(mut ui_query: Query<(&UiTag, &mut Visibility)>) {
-    for (tag, mut vis) in &mut ui_query {
+fn next_round(mut match_timers: ResMut<MatchTimers>) {
+    match_timers.game.reset();
+    match_timers.lag = 0.0;
+}
+
+fn reset_ui(mut ui_query: Query<(&UiTag, &mut Visibility, &mut Text)>) {
+    for (tag, mut vis, mut text) in &mut ui_query {
         if tag.name == UiName::Win || tag.name == UiName::Lose {
             vis.is_visible = false;
         }
+        if tag.name == UiName::Recipe {
+            text.sections[0].value.clear();
+        }
     }
 }
 
@@ -929,69 +1520,79 @@ fn tally(
     mut commands: Commands,
     obj_query: Query<(Entity, &Objective)>,
     mut dude_query: Query<&mut Dude>,
-    mut score: ResMut<Score>,
-    mut difficulty: ResMut<Difficulty>,
+    mut kitchen: ResMut<Kitchen>,
     mut smash_event: EventReader<PanSmashEvent>,
     mut ui_query: Query<(&UiTag, &mut Visibility)>,
     mut refresh: EventWriter<RefreshShelfEvent>,
+    mut served_event: EventWriter<ServedEvent>,
+    mut recipe_text: Query<(&UiTag, &mut Text)>,
+    info: Res<Info>,
 ) {
     for _ in smash_event.iter() {
         let mut dude = dude_query.single_mut();
 
-        let mut sum: f32 = 0.0;
-        for (ent, obj) in &obj_query {
-            let modifier = dude.palate.0.get(&obj.taste).unwrap_or(&1.0);
-            let value = 1.0 * modifier;
-            sum += value;
+        let tally = kitchen.smash(&info.recipe);
+        let name = if tally.success {
+            UiName::Win
+        } else {
+            UiName::Lose
+        };
+        for (tag, mut text) in &mut recipe_text {
+            if tag.name == UiName::Recipe {
+                text.sections[0].value = tally.recipes.join(" + ");
+            } else if tag.name == name {
+                text.sections[1].value = format!("\n{} / {}", tally.sum as i32, tally.threshold);
+            }
+        }
+        served_event.send(ServedEvent {
+            dude: dude.info.name.clone(),
+            success: tally.success,
+            recipes: tally.recipes,
+        });
+        for (ent, _) in &obj_query {
             commands.entity(ent).despawn();
             dude.cycles = 0;
         }
 
         refresh.send(RefreshShelfEvent { clear: false });
 
-        if sum as i32 >= difficulty.threshold {
-            score.successes += 1;
-            difficulty.threshold += 10;
-            if let Some((_, mut vis)) = ui_query
-                .iter_mut()
-                .find(|(tag, _)| tag.name == UiName::Win)
-                .take()
-            {
-                vis.is_visible = true;
-            }
-        } else {
-            score.losses += 1;
-            difficulty.threshold -= 3;
-            if let Some((_, mut vis)) = ui_query
-                .iter_mut()
-                .find(|(tag, _)| tag.name == UiName::Lose)
-                .take()
-            {
-                vis.is_visible = true;
-            }
+        if let Some((_, mut vis)) = ui_query.iter_mut().find(|(tag, _)| tag.name == name).take() {
+            vis.is_visible = true;
         }
     }
 }
 
-fn update_ui(
-    mut text_query: Query<(&mut Text, &UiTag)>,
-    obj_query: Query<&Objective>,
-    difficulty: Res<Difficulty>,
-) {
-    let mut stomach_sum = 0;
-    let mut pan_sum = 0;
-    for obj in &obj_query {
-        if obj.zone == ObjectiveZone::Stomach {
-            stomach_sum += 1
-        } else {
-            pan_sum += 1
-        }
-    }
+fn update_ui(mut text_query: Query<(&mut Text, &UiTag)>, kitchen: Res<Kitchen>, info: Res<Info>) {
     for (mut text, tag) in &mut text_query {
         match tag.name {
-            UiName::Palate => text.sections[0].value = difficulty.threshold.to_string(),
-            UiName::Stomach => text.sections[0].value = stomach_sum.to_string(),
-            UiName::Pan => text.sections[0].value = pan_sum.to_string(),
+            UiName::Palate => text.sections[0].value = kitchen.difficulty.threshold.to_string(),
+            UiName::Stomach => {
+                text.sections[0].value = match kitchen.customer() {
+                    Some(customer) => {
+                        format!("{}/{}", customer.stomach.len(), customer.info.capacity)
+                    }
+                    None => "0".to_string(),
+                }
+            }
+            UiName::Pan => text.sections[0].value = kitchen.pan.len().to_string(),
+            UiName::Projection => {
+                let projected = kitchen.projected(&info.recipe);
+                let (worst, best) = kitchen.next_tick_range(&info.interaction);
+                text.sections[0].value =
+                    format!("{} / {}\n", projected as i32, kitchen.difficulty.threshold);
+                text.sections[0].style.color = if projected as i32 >= kitchen.difficulty.threshold {
+                    Color::DARK_GREEN
+                } else {
+                    Color::RED
+                };
+                text.sections[1].value = format!("next tick {:+} / {:+}", worst, best);
+            }
+            UiName::Lives => {
+                text.sections[0].value = match kitchen.lives_left() {
+                    Some(lives) => "lives ".to_string() + &lives.to_string(),
+                    None => "".to_string(),
+                }
+            }
             _ => (),
         }
     }
@@ -1008,7 +1609,7 @@ fn update_ui_timer(
         theme_lol.reward.percent()
     };
     for (uitimer, mut vis) in &mut ui_timer_query {
-        if uitimer.num < (perc * 10.0) as u32 {
+        if uitimer.num < (perc * uitimer.count as f32) as u32 {
             vis.is_visible = false;
         } else {
             vis.is_visible = true;
@@ -1024,14 +1625,18 @@ fn ready_anim_objs(mut obj_query: Query<&mut Objective>) {
     }
 }
 
-fn pan_reset(mut pan_query: Query<&mut Pan>) {
-    let mut pan = pan_query.single_mut();
+fn pan_reset(mut pan_query: Query<(&mut Pan, &mut Transform, &mut TextureAtlasSprite)>) {
+    let (mut pan, mut tr, mut sprite) = pan_query.single_mut();
+    pan.timer.reset();
     pan.smashed = false;
+    tr.translation = pan.from;
+    sprite.index = 0;
 }
 
 fn pan_anim(
     mut obj_query: Query<&mut Objective>,
     mut pan_query: Query<(&mut Pan, &mut Transform, &mut TextureAtlasSprite)>,
+    mouth_query: Query<&Transform, (With<MouthPoint>, Without<Pan>)>,
     mut event_smash: EventWriter<PanSmashEvent>,
     time: Res<Time>,
     audio_handles: Res<AudioHandles>,
@@ -1061,7 +1666,12 @@ fn pan_anim(
             dude.yeah = true;
         }
         let e = f32::min(1.0, t.powi(5) * 32.0);
-        tr.translation = pan.from * (1.0 - e) + pan.goto * e;
+        let goto = mouth_query
+            .single()
+            .translation
+            .truncate()
+            .extend(pan.from.z);
+        tr.translation = pan.from * (1.0 - e) + goto * e;
         let atlas = assets
             .get(atlas_handles.handles.get("pan").unwrap())
             .unwrap();
@@ -1102,32 +1712,96 @@ fn cooking(
     mut commands: Commands,
     obj_points: Query<(&ObjectivePoint, &Transform)>,
     mut obj_query: Query<(Entity, &mut Objective, &Transform)>,
+    mut kitchen: ResMut<Kitchen>,
     mut match_timers: ResMut<MatchTimers>,
     atlas_handles: ResMut<AtlasHandles>,
     time: Res<Time>,
+    mut rngs: ResMut<Rngs>,
+    playback: Option<Res<Playback>>,
+    info: Res<Info>,
+    asset_server: Res<AssetServer>,
 ) {
-    let mut rng = thread_rng();
-    match_timers.cook.tick(time.delta());
-    if match_timers.cook.finished() {
-        let mut objs = Vec::<(Entity, Taste)>::new();
-        for (ent, obj, _) in &obj_query {
-            objs.push((ent, obj.taste.clone()));
+    let rngs = &mut *rngs;
+    let rng = &mut rngs.visual;
+    let until = playback.and_then(|playback| playback.next_step());
+    let mut events = vec![];
+    for _ in 0..match_timers.due(time.delta_seconds(), until) {
+        match_timers.steps += 1;
+        match_timers.game.tick(Duration::from_secs_f32(STEP));
+        events.extend(kitchen.tick(STEP, &info.interaction, &mut rngs.cooking));
+        if match_timers.game.finished() || kitchen.is_over() {
+            match_timers.lag = 0.0;
+            break;
         }
-        for (ent, mut obj, tr) in &mut obj_query {
-            if obj.zone == ObjectiveZone::Stomach {
-                if rng.gen_ratio(1, 25) {
+    }
+    for event in events {
+        match event {
+            TickEvent::Reacted {
+                interaction,
+                at,
+                removed,
+            } => {
+                if let Some((_, _, tr)) = obj_query.iter().find(|(_, obj, _)| obj.id == at) {
+                    commands
+                        .spawn_bundle(Text2dBundle {
+                            text: Text::from_section(
+                                format!(
+                                    "{} {} {}",
+                                    interaction.taste.as_str(),
+                                    interaction.effect.as_str(),
+                                    interaction.with.as_str()
+                                ),
+                                TextStyle {
+                                    font: asset_server.load("fonts/SztyletBd.ttf"),
+                                    font_size: 32.0,
+                                    color: Color::BLACK,
+                                },
+                            )
+                            .with_alignment(TextAlignment::CENTER),
+                            transform: Transform::from_translation(
+                                tr.translation.truncate().extend(9.0),
+                            ),
+                            ..default()
+                        })
+                        .insert(ReactionLabel {
+                            timer: Timer::new(Duration::from_millis(1000), false),
+                        });
+                }
+                for (ent, _, _) in obj_query
+                    .iter()
+                    .filter(|(_, obj, _)| removed.contains(&obj.id))
+                {
                     commands.entity(ent).despawn();
                 }
             }
-            if obj.zone == ObjectiveZone::Pan {
-                if let Some(_) = objs
-                    .iter()
-                    .find(|(oth, taste)| *oth != ent && obj.taste == *taste)
-                    .take()
+            TickEvent::Left(customer) => {
+                commands
+                    .spawn_bundle(Text2dBundle {
+                        text: Text::from_section(
+                            format!("{} left!", customer.info.name),
+                            TextStyle {
+                                font: asset_server.load("fonts/SztyletBd.ttf"),
+                                font_size: 48.0,
+                                color: Color::RED,
+                            },
+                        )
+                        .with_alignment(TextAlignment::CENTER),
+                        transform: Transform::from_xyz(0.0, 160.0, 9.0),
+                        ..default()
+                    })
+                    .insert(ReactionLabel {
+                        timer: Timer::new(Duration::from_millis(1000), false),
+                    });
+            }
+            TickEvent::Digested(id) => {
+                if let Some((ent, _, _)) = obj_query.iter().find(|(_, obj, _)| obj.id == id) {
+                    commands.entity(ent).despawn();
+                }
+            }
+            TickEvent::Duplicated { from, token } => {
+                if let Some((_, mut obj, tr)) =
+                    obj_query.iter_mut().find(|(_, obj, _)| obj.id == from)
                 {
-                    if rng.gen_ratio(24, 25) {
-                        continue;
-                    }
                     let (_, objtr) = obj_points
                         .iter()
                         .find(|(pt, _)| pt.zone == obj.zone)
@@ -1142,7 +1816,7 @@ fn cooking(
                             rng.gen_range(-60..80) as f32,
                             rng.gen_range(0..10000) as f32 / 100000.0,
                         );
-                    let atlas_handle = atlas_handles.handles.get(obj.taste.as_str()).unwrap();
+                    let atlas_handle = atlas_handles.handles.get(token.taste.as_str()).unwrap();
                     commands
                         .spawn_bundle(SpriteSheetBundle {
                             sprite: TextureAtlasSprite::new(0),
@@ -1160,9 +1834,9 @@ fn cooking(
                             ..default()
                         })
                         .insert(Objective {
+                            id: token.id,
                             from,
                             goto,
-                            taste: obj.taste.clone(),
                             zone: obj.zone.clone(),
                             timer: Timer::new(Duration::from_millis(500), false),
                         });
@@ -1172,6 +1846,29 @@ fn cooking(
     }
 }
 
+#[derive(Component)]
+struct ReactionLabel {
+    timer: Timer,
+}
+
+fn reaction_labels(
+    mut commands: Commands,
+    mut label_query: Query<(Entity, &mut ReactionLabel, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (ent, mut label, mut tr, mut text) in &mut label_query {
+        label.timer.tick(time.delta());
+        tr.translation.y += 40.0 * time.delta_seconds();
+        text.sections[0]
+            .style
+            .color
+            .set_a(1.0 - label.timer.percent());
+        if label.timer.finished() {
+            commands.entity(ent).despawn();
+        }
+    }
+}
+
 fn eat_anim(
     mut eat_event_read: EventReader<EatEvent>,
     mut dude_query: Query<(&mut Dude, &mut TextureAtlasSprite)>,
@@ -1181,16 +1878,10 @@ fn eat_anim(
 ) {
     let (mut dude, mut id) = dude_query.single_mut();
     for event in eat_event_read.iter() {
-        dude.puking = false;
-        for i in 0..8 {
-            let taste = Taste::from_u32(i);
-            let val = *event.flavor.0.get(&taste).unwrap_or(&0.0);
-            let pref = *dude.palate.0.get(&taste).unwrap_or(&0.0);
-            if Preference::from_f32(pref) == Preference::Dislike && val > 0.0 {
-                dude.puking = true;
-                let audio_name = dude.info.sprite.clone() + "-puke";
-                audio.play(audio_handles.handles.get(&audio_name).unwrap().clone());
-            }
+        dude.puking = event.puke;
+        if event.puke {
+            let audio_name = dude.info.sprite.clone() + "-puke";
+            audio.play(audio_handles.handles.get(&audio_name).unwrap().clone());
         }
         dude.cycles += 4;
         dude.timer.reset();
@@ -1222,123 +1913,215 @@ fn eat_anim(
 fn spawn_objectives(
     mut commands: Commands,
     obj_points: Query<(&ObjectivePoint, &Transform)>,
+    obj_query: Query<(Entity, &Objective)>,
     atlas_handles: ResMut<AtlasHandles>,
     mut eat_event: EventReader<EatEvent>,
+    mut rngs: ResMut<Rngs>,
 ) {
-    let mut rng = thread_rng();
+    let rng = &mut rngs.visual;
     for event in eat_event.iter() {
-        for i in 0..8 {
-            let taste = Taste::from_u32(i);
-            let value = *event.flavor.0.get(&taste).unwrap_or(&0.0);
-
-            let (_, objtr) = obj_points
-                .iter()
-                .find(|(pt, _)| pt.zone == event.to_zone)
-                .take()
-                .unwrap();
+        if event.overate {
+            for (ent, obj) in &obj_query {
+                if obj.zone == ObjectiveZone::Stomach {
+                    commands.entity(ent).despawn();
+                }
+            }
+        }
+        let (_, objtr) = obj_points
+            .iter()
+            .find(|(pt, _)| pt.zone == event.to_zone)
+            .take()
+            .unwrap();
 
-            for _ in 0..(value as u32) {
-                let from = event.from
+        for token in event.tokens.iter() {
+            let from = event.from
+                + Vec3::new(
+                    rng.gen_range(-100..100) as f32,
+                    rng.gen_range(-100..100) as f32,
+                    0.1,
+                );
+            let goto = if event.to_zone == ObjectiveZone::Stomach {
+                objtr.translation
                     + Vec3::new(
-                        rng.gen_range(-100..100) as f32,
-                        rng.gen_range(-100..100) as f32,
-                        0.1,
-                    );
-                let goto = if event.to_zone == ObjectiveZone::Stomach {
-                    objtr.translation
-                        + Vec3::new(
-                            rng.gen_range(-20..20) as f32,
-                            rng.gen_range(-10..10) as f32,
-                            rng.gen_range(0..1000) as f32 / 10000.0,
-                        )
-                } else {
-                    objtr.translation
-                        + Vec3::new(
-                            rng.gen_range(-150..150) as f32,
-                            rng.gen_range(-60..80) as f32,
-                            rng.gen_range(0..10000) as f32 / 100000.0,
-                        )
-                };
+                        rng.gen_range(-20..20) as f32,
+                        rng.gen_range(-10..10) as f32,
+                        rng.gen_range(0..1000) as f32 / 10000.0,
+                    )
+            } else {
+                objtr.translation
+                    + Vec3::new(
+                        rng.gen_range(-150..150) as f32,
+                        rng.gen_range(-60..80) as f32,
+                        rng.gen_range(0..10000) as f32 / 100000.0,
+                    )
+            };
 
-                let atlas_handle = atlas_handles.handles.get(taste.as_str()).unwrap();
-                commands
-                    .spawn_bundle(SpriteSheetBundle {
-                        sprite: TextureAtlasSprite::new(0),
-                        texture_atlas: atlas_handle.clone(),
-                        transform: Transform {
-                            translation: event.from,
-                            scale: Vec3::new(0.5, 0.5, 0.5),
-                            ..default()
-                        },
+            let atlas_handle = atlas_handles.handles.get(token.taste.as_str()).unwrap();
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite::new(0),
+                    texture_atlas: atlas_handle.clone(),
+                    transform: Transform {
+                        translation: event.from,
+                        scale: Vec3::new(0.5, 0.5, 0.5),
                         ..default()
-                    })
-                    .insert(Objective {
-                        from,
-                        goto,
-                        taste: taste.clone(),
-                        zone: event.to_zone.clone(),
-                        timer: Timer::new(Duration::from_millis(500), false),
+                    },
+                    ..default()
+                })
+                .insert(Objective {
+                    id: token.id,
+                    from,
+                    goto,
+                    zone: event.to_zone.clone(),
+                    timer: Timer::new(Duration::from_millis(500), false),
+                });
+        }
+    }
+}
+
+fn mouse_hand(
+    mut mouse_button_input_events: EventReader<MouseButtonInput>,
+    mut dragging: Local<bool>,
+    mousepos: Res<MousePos>,
+    hand: Res<Hand>,
+    food_query: Query<(&Food, &Transform)>,
+    shelves: Query<&Shelf>,
+    pan_query: Query<(&Pan, &Transform)>,
+    mouth_query: Query<(&MouthPoint, &Transform)>,
+    waiting_query: Query<(&Waiting, &Transform)>,
+    playback: Option<Res<Playback>>,
+    mut hand_action: EventWriter<HandAction>,
+) {
+    for event in mouse_button_input_events.iter() {
+        if playback.is_some() {
+            continue;
+        }
+        match (event.button, event.state) {
+            (MouseButton::Left, ButtonState::Pressed) => {
+                if hand.holding.is_some() {
+                    *dragging = true;
+                } else if let Some(food) = shelved_food_at(mousepos.world, &food_query) {
+                    let shelf = shelves.get(food.shelf).unwrap();
+                    hand_action.send(HandAction::Take { shelf: shelf.index });
+                    *dragging = true;
+                } else if let Some(customer) = waiting_at(mousepos.world, &waiting_query) {
+                    hand_action.send(HandAction::Select { customer });
+                }
+            }
+            (MouseButton::Left, ButtonState::Released) => {
+                if *dragging {
+                    *dragging = false;
+                    let (_mouth, mouth_tr) = mouth_query.get_single().unwrap();
+                    let (_pan, pan_tr) = pan_query.get_single().unwrap();
+                    hand_action.send(match drop_zone(mousepos.world, mouth_tr, pan_tr) {
+                        Some(zone) => HandAction::Drop { zone },
+                        None => HandAction::Return,
                     });
+                }
+            }
+            (MouseButton::Right, ButtonState::Pressed) => {
+                if hand.holding.is_some() {
+                    *dragging = false;
+                    hand_action.send(HandAction::Return);
+                }
             }
+            _ => (),
         }
     }
 }
 
+fn drop_zone(world: Vec2, mouth_tr: &Transform, pan_tr: &Transform) -> Option<ObjectiveZone> {
+    if world.distance_squared(mouth_tr.translation.truncate()) < 150.0 * 150.0 {
+        Some(ObjectiveZone::Stomach)
+    } else if world.distance_squared(pan_tr.translation.truncate()) < 200.0 * 200.0 {
+        Some(ObjectiveZone::Pan)
+    } else {
+        None
+    }
+}
+
+fn shelved_food_at<'a>(
+    world: Vec2,
+    food_query: &'a Query<(&Food, &Transform)>,
+) -> Option<&'a Food> {
+    food_query
+        .iter()
+        .find(|(food, tr)| {
+            food.state == FoodState::Shelved
+                && world.distance_squared(tr.translation.truncate()) < 100.0 * 100.0
+        })
+        .map(|(food, _)| food)
+}
+
+fn waiting_at(world: Vec2, waiting_query: &Query<(&Waiting, &Transform)>) -> Option<u32> {
+    waiting_query
+        .iter()
+        .find(|(_, tr)| world.distance_squared(tr.translation.truncate()) < 80.0 * 80.0)
+        .map(|(waiting, _)| waiting.customer)
+}
+
 fn use_hand(
     mut commands: Commands,
-    mut mouse_button_input_events: EventReader<MouseButtonInput>,
-    mousepos: Res<MousePos>,
+    mut hand_action: EventReader<HandAction>,
     mut hand: ResMut<Hand>,
     mut food_query: Query<(Entity, &mut Food, &Transform)>,
-    pan_query: Query<(&Pan, &Transform)>,
-    mouth_query: Query<(&MouthPoint, &Transform)>,
+    shelves: Query<&Shelf>,
     mut refresh_event: EventWriter<RefreshShelfEvent>,
     mut eat_event: EventWriter<EatEvent>,
+    mut kitchen: ResMut<Kitchen>,
 ) {
-    for _event in mouse_button_input_events.iter() {
-        if let Some(held) = hand.holding {
-            let (_mouth, mouth_tr) = mouth_query.get_single().unwrap();
-            let (_pan, pan_tr) = pan_query.get_single().unwrap();
-            if mousepos
-                .world
-                .distance_squared(mouth_tr.translation.truncate())
-                < 150.0 * 150.0
-            {
-                let (ent, mut food, tr) = food_query.get_mut(held).unwrap();
-                food.state = FoodState::Eaten;
-                refresh_event.send(RefreshShelfEvent { clear: true });
-                eat_event.send(EatEvent {
-                    from: tr.translation,
-                    flavor: food.info.flavor.clone(),
-                    to_zone: ObjectiveZone::Stomach,
-                });
-                hand.holding = None;
-                commands.entity(ent).despawn();
-            } else if mousepos
-                .world
-                .distance_squared(pan_tr.translation.truncate())
-                < 200.0 * 200.0
-            {
-                let (ent, mut food, tr) = food_query.get_mut(held).unwrap();
-                food.state = FoodState::Cooking;
-                refresh_event.send(RefreshShelfEvent { clear: false });
-                eat_event.send(EatEvent {
-                    from: tr.translation,
-                    flavor: food.info.flavor.clone(),
-                    to_zone: ObjectiveZone::Pan,
-                });
-                hand.holding = None;
-                commands.entity(ent).despawn();
+    for action in hand_action.iter() {
+        match action {
+            HandAction::Take { shelf } => {
+                if hand.holding.is_some() {
+                    continue;
+                }
+                for (ent, mut food, _) in &mut food_query {
+                    if food.state == FoodState::Shelved
+                        && shelves.get(food.shelf).unwrap().index == *shelf
+                    {
+                        food.state = FoodState::Held;
+                        hand.holding = Some(ent);
+                    }
+                }
             }
-        } else {
-            for (ent, mut food, tr) in &mut food_query {
-                if food.state == FoodState::Shelved
-                    && mousepos.world.distance_squared(tr.translation.truncate()) < 100.0 * 100.0
-                {
-                    food.state = FoodState::Held;
-                    hand.holding = Some(ent);
+            HandAction::Drop { zone } => {
+                if let Some(held) = hand.holding {
+                    let (ent, mut food, tr) = food_query.get_mut(held).unwrap();
+                    let bite = if *zone == ObjectiveZone::Stomach {
+                        food.state = FoodState::Eaten;
+                        refresh_event.send(RefreshShelfEvent { clear: true });
+                        kitchen.eat(&food.info)
+                    } else {
+                        food.state = FoodState::Cooking;
+                        refresh_event.send(RefreshShelfEvent { clear: false });
+                        kitchen.cook(&food.info)
+                    };
+                    eat_event.send(EatEvent {
+                        from: tr.translation,
+                        tokens: bite.tokens,
+                        puke: bite.puke,
+                        overate: bite.overate,
+                        to_zone: zone.clone(),
+                    });
+                    hand.holding = None;
+                    commands.entity(ent).despawn();
+                }
+            }
+            HandAction::Return => {
+                if let Some(held) = hand.holding {
+                    let (ent, mut food, tr) = food_query.get_mut(held).unwrap();
+                    food.state = FoodState::Returning;
+                    commands.entity(ent).insert(ReturnAnim {
+                        from: tr.translation,
+                        timer: Timer::new(Duration::from_millis(250), false),
+                    });
+                    hand.holding = None;
                 }
             }
+            HandAction::Select { customer } => {
+                kitchen.select(*customer);
+            }
         }
     }
 }
@@ -1347,7 +2130,11 @@ fn move_hand(
     mut food_query: Query<(&Food, &mut Transform)>,
     mousepos: Res<MousePos>,
     hand: Res<Hand>,
+    drag: Res<touch::TouchDrag>,
 ) {
+    if drag.touch.is_some() {
+        return;
+    }
     if let Some(held) = hand.holding {
         if let Some((_, mut tr)) = food_query.get_mut(held).ok() {
             tr.translation = Vec3::new(mousepos.world.x, mousepos.world.y, 3.0);
@@ -1355,6 +2142,24 @@ fn move_hand(
     }
 }
 
+fn return_anim(
+    mut commands: Commands,
+    mut food_query: Query<(Entity, &mut Food, &mut ReturnAnim, &mut Transform)>,
+    shelves: Query<&Transform, (With<Shelf>, Without<Food>)>,
+    time: Res<Time>,
+) {
+    for (ent, mut food, mut anim, mut tr) in &mut food_query {
+        let shelf_tr = shelves.get(food.shelf).unwrap();
+        anim.timer.tick(time.delta());
+        let t = anim.timer.percent();
+        tr.translation = anim.from * (1.0 - t) + shelf_tr.translation * t;
+        if anim.timer.finished() {
+            food.state = FoodState::Shelved;
+            commands.entity(ent).remove::<ReturnAnim>();
+        }
+    }
+}
+
 fn clear_hand(mut commands: Commands, mut hand: ResMut<Hand>) {
     if let Some(held) = hand.holding {
         commands.entity(held).despawn();
@@ -1362,30 +2167,10 @@ fn clear_hand(mut commands: Commands, mut hand: ResMut<Hand>) {
     hand.holding = None;
 }
 
-#[derive(PartialEq, Clone)]
-enum Preference {
-    Like,
-    Dislike,
-    Resist,
-    Normal,
-}
-
-impl Preference {
-    fn from_f32(n: f32) -> Self {
-        if n < 0.0 {
-            Preference::Dislike
-        } else if n > 1.0 {
-            Preference::Like
-        } else if n > 0.0 {
-            Preference::Normal
-        } else {
-            Preference::Resist
-        }
-    }
-}
-
 #[derive(Component)]
-struct PreferenceToken;
+struct PreferenceToken {
+    hidden: bool,
+}
 
 #[derive(Component)]
 struct DudePreferencePoint {
@@ -1395,10 +2180,23 @@ struct DudePreferencePoint {
 struct DudePoint;
 
 #[derive(Component)]
-struct Shelf;
+struct Waiting {
+    customer: u32,
+}
+
+#[derive(Component)]
+struct PatienceLabel {
+    customer: u32,
+}
+
+#[derive(Component)]
+struct Shelf {
+    index: usize,
+}
 
 fn refresh_shelf(
     mut commands: Commands,
+    kitchen: Res<Kitchen>,
     foods: Query<(Entity, &Food)>,
     shelves: Query<(Entity, &Shelf)>,
     mut refresh_event: EventReader<RefreshShelfEvent>,
@@ -1407,7 +2205,7 @@ fn refresh_shelf(
     for event in refresh_event.iter() {
         let mut occupied: Vec<Entity> = vec![];
         for (ent, food) in &foods {
-            if food.state == FoodState::Shelved {
+            if food.state == FoodState::Shelved || food.state == FoodState::Returning {
                 if event.clear {
                     occupied.push(food.shelf);
                 } else {
@@ -1415,8 +2213,12 @@ fn refresh_shelf(
                 }
             }
         }
-        for (ent, _shelf) in &shelves {
-            if !occupied.contains(&ent) {
+        let first = shelves
+            .iter()
+            .count()
+            .saturating_sub(kitchen.profile.shelves);
+        for (ent, shelf) in &shelves {
+            if shelf.index >= first && !occupied.contains(&ent) {
                 restock_event.send(RestockShelfEvent { shelf: ent });
             }
         }
@@ -1429,12 +2231,12 @@ fn restock_shelf(
     mut restock_event: EventReader<RestockShelfEvent>,
     atlas_handles: ResMut<AtlasHandles>,
     info: Res<Info>,
+    mut rngs: ResMut<Rngs>,
 ) {
     for event in restock_event.iter() {
         let (ent, _shelf, tr) = shelves.get(event.shelf).unwrap();
 
-        let mut rng = thread_rng();
-        let food_info = &info.food[rng.gen_range(0..info.food.len())];
+        let food_info = &info.food[rngs.shelf.gen_range(0..info.food.len())];
 
         let atlas_handle = atlas_handles.handles.get(&food_info.sprite).unwrap();
         commands
@@ -0,0 +1,319 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::save::Save;
+use crate::sim::Kitchen;
+use crate::{AppState, Info, Mode, Restart};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component, Clone, Copy)]
+pub enum MenuButton {
+    Play,
+    HighScores,
+    Recipes,
+    HowToPlay,
+    Easy,
+    Normal,
+    Hard,
+    Adaptive,
+    Back,
+    Resume,
+    Restart,
+    GiveUp,
+    Menu,
+    Quit,
+}
+
+impl MenuButton {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::Play => "play",
+            MenuButton::HighScores => "high scores",
+            MenuButton::Recipes => "recipes",
+            MenuButton::HowToPlay => "how to play",
+            MenuButton::Easy => "easy",
+            MenuButton::Normal => "normal",
+            MenuButton::Hard => "hard",
+            MenuButton::Adaptive => "adaptive",
+            MenuButton::Back => "back",
+            MenuButton::Resume => "resume",
+            MenuButton::Restart => "restart",
+            MenuButton::GiveUp => "give up",
+            MenuButton::Menu => "menu",
+            MenuButton::Quit => "quit",
+        }
+    }
+}
+
+fn spawn_menu(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    title: &str,
+    lines: &[String],
+    buttons: &[MenuButton],
+) {
+    let font = asset_server.load("fonts/SztyletBd.ttf");
+    let style = TextStyle {
+        font: font.clone(),
+        font_size: 48.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 144.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+            for line in lines {
+                parent.spawn_bundle(TextBundle::from_section(line, style.clone()));
+            }
+            for button in buttons {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(300.0), Val::Px(72.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(*button)
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle::from_section(button.label(), style.clone()));
+                    });
+            }
+        });
+}
+
+pub fn main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut buttons = vec![
+        MenuButton::Play,
+        MenuButton::HighScores,
+        MenuButton::Recipes,
+        MenuButton::HowToPlay,
+    ];
+    if cfg!(not(target_arch = "wasm32")) {
+        buttons.push(MenuButton::Quit);
+    }
+    spawn_menu(&mut commands, &asset_server, "perfect chef", &[], &buttons);
+}
+
+pub fn pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "paused",
+        &[],
+        &[
+            MenuButton::Resume,
+            MenuButton::Restart,
+            MenuButton::GiveUp,
+            MenuButton::Menu,
+        ],
+    );
+}
+
+pub fn game_over_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    kitchen: Res<Kitchen>,
+    save: Res<Save>,
+) {
+    let mut lines = vec![
+        format!("customers served: {}", kitchen.score.served),
+        format!("happy customers: {}", kitchen.score.successes),
+        format!("highest goal: {}", kitchen.score.peak_threshold),
+    ];
+    if save.new_best {
+        lines.push("new best!".to_string());
+    }
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "game over",
+        &lines,
+        &[MenuButton::Restart, MenuButton::Menu],
+    );
+}
+
+pub fn high_scores_menu(mut commands: Commands, asset_server: Res<AssetServer>, save: Res<Save>) {
+    let mut lines = vec![
+        format!(
+            "best run: {} happy customers, goal {}",
+            save.best.successes, save.best.peak_threshold
+        ),
+        format!(
+            "foods eaten: {}, cooked: {}",
+            save.foods_eaten, save.foods_cooked
+        ),
+        format!("pukes: {}, pans smashed: {}", save.pukes, save.pan_smashes),
+    ];
+    for (dude, stats) in save.dudes.iter() {
+        lines.push(format!(
+            "{}: {}/{} happy, {} pukes",
+            dude, stats.successes, stats.served, stats.pukes
+        ));
+    }
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "high scores",
+        &lines,
+        &[MenuButton::Back],
+    );
+}
+
+pub fn recipe_book_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    info: Res<Info>,
+    save: Res<Save>,
+) {
+    let lines: Vec<String> = info
+        .recipe
+        .iter()
+        .map(|recipe| {
+            if !save.recipes.contains(&recipe.name) {
+                return vec!["?"; recipe.ingredients.len()].join(" + ");
+            }
+            let mut line = recipe.name.clone() + ": " + &recipe.ingredients.join(" + ");
+            if recipe.bonus != 0.0 {
+                line += &format!(" {:+}", recipe.bonus);
+            }
+            if recipe.multiplier != 1.0 {
+                line += &format!(" x{}", recipe.multiplier);
+            }
+            line
+        })
+        .collect();
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "recipes",
+        &lines,
+        &[MenuButton::Back],
+    );
+}
+
+pub fn mode_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "difficulty",
+        &[],
+        &[
+            MenuButton::Easy,
+            MenuButton::Normal,
+            MenuButton::Hard,
+            MenuButton::Adaptive,
+            MenuButton::Back,
+        ],
+    );
+}
+
+pub fn how_to_play_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let lines: Vec<String> = [
+        "drag food to the mouth to feed it, or to the pan to cook it",
+        "when the timer runs out the pan is served, beat the goal to win",
+        "liked tastes score double, disliked tastes make the customer puke",
+        "resisted tastes score nothing, but they never multiply in the pan",
+        "grey tastes are hidden until the customer eats them",
+        "overeating makes the customer puke and costs points",
+        "click a waiting customer or press tab to serve them instead",
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect();
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        "how to play",
+        &lines,
+        &[MenuButton::Back],
+    );
+}
+
+pub fn despawn_menu(mut commands: Commands, menu_query: Query<Entity, With<MenuRoot>>) {
+    for ent in &menu_query {
+        commands.entity(ent).despawn_recursive();
+    }
+}
+
+pub fn menu_buttons(
+    mut restart: ResMut<Restart>,
+    mut mode: ResMut<Mode>,
+    mut state: ResMut<State<AppState>>,
+    mut interaction_query: Query<
+        (&Interaction, &MenuButton, &mut UiColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button, mut color) in &mut interaction_query {
+        match interaction {
+            Interaction::Clicked => match button {
+                MenuButton::Play => state.overwrite_set(AppState::ChooseMode).unwrap(),
+                MenuButton::Easy | MenuButton::Normal | MenuButton::Hard | MenuButton::Adaptive => {
+                    mode.difficulty = button.label().to_string();
+                    restart.0 = true;
+                    state.overwrite_replace(AppState::Game).unwrap();
+                }
+                MenuButton::Restart => {
+                    restart.0 = true;
+                    state.overwrite_replace(AppState::Game).unwrap();
+                }
+                MenuButton::HighScores => state.overwrite_set(AppState::HighScores).unwrap(),
+                MenuButton::Recipes => state.overwrite_set(AppState::RecipeBook).unwrap(),
+                MenuButton::HowToPlay => state.overwrite_set(AppState::HowToPlay).unwrap(),
+                MenuButton::Back => state.overwrite_set(AppState::MainMenu).unwrap(),
+                MenuButton::Resume => state.overwrite_pop().unwrap(),
+                MenuButton::GiveUp => state.overwrite_replace(AppState::GameOver).unwrap(),
+                MenuButton::Menu => state.overwrite_replace(AppState::MainMenu).unwrap(),
+                MenuButton::Quit => exit.send(AppExit),
+            },
+            Interaction::Hovered => *color = HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+pub fn pause_key(keys: Res<Input<KeyCode>>, mut state: ResMut<State<AppState>>) {
+    if keys.just_pressed(KeyCode::Escape) || keys.just_pressed(KeyCode::P) {
+        if state.current() == &AppState::Paused {
+            state.pop().unwrap();
+        } else {
+            state.push(AppState::Paused).unwrap();
+        }
+    }
+}
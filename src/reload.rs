@@ -0,0 +1,239 @@
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+
+use crate::data::{
+    self, DifficultyCatalog, DudeCatalog, FoodCatalog, InteractionTable, RecipeCatalog,
+};
+use crate::sim::{DifficultyProfile, DudeInfo, FoodInfo, Interaction, Kitchen, RecipeInfo};
+use crate::{
+    build_atlas, AppState, AtlasHandles, AudioHandles, DataHandles, Dude, Food, FoodState, Info,
+    RawHandles, RestockShelfEvent,
+};
+
+type Catalogs = (
+    Vec<FoodInfo>,
+    Vec<DudeInfo>,
+    Vec<RecipeInfo>,
+    Vec<Interaction>,
+    Vec<DifficultyProfile>,
+);
+
+#[derive(Default)]
+pub struct PendingReload {
+    catalogs: Option<Catalogs>,
+    atlases: Vec<Vec<String>>,
+    handles: Vec<HandleId>,
+}
+
+pub fn watch_data(
+    state: Res<State<AppState>>,
+    mut food_events: EventReader<AssetEvent<FoodCatalog>>,
+    mut dude_events: EventReader<AssetEvent<DudeCatalog>>,
+    mut recipe_events: EventReader<AssetEvent<RecipeCatalog>>,
+    mut interaction_events: EventReader<AssetEvent<InteractionTable>>,
+    mut difficulty_events: EventReader<AssetEvent<DifficultyCatalog>>,
+    data_handles: Option<Res<DataHandles>>,
+    food_catalogs: Res<Assets<FoodCatalog>>,
+    dude_catalogs: Res<Assets<DudeCatalog>>,
+    recipe_catalogs: Res<Assets<RecipeCatalog>>,
+    interaction_tables: Res<Assets<InteractionTable>>,
+    difficulty_catalogs: Res<Assets<DifficultyCatalog>>,
+    asset_server: Res<AssetServer>,
+    mut raw_handles: ResMut<RawHandles>,
+    mut audio_handles: ResMut<AudioHandles>,
+    mut pending: ResMut<PendingReload>,
+) {
+    let food_modified = food_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let dude_modified = dude_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let recipe_modified = recipe_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let interaction_modified = interaction_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let difficulty_modified = difficulty_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !food_modified
+        && !dude_modified
+        && !recipe_modified
+        && !interaction_modified
+        && !difficulty_modified
+    {
+        return;
+    }
+    if matches!(state.current(), AppState::Init | AppState::Setup) {
+        return;
+    }
+    let data_handles = data_handles.unwrap();
+    let (food, dude, recipe, interaction, difficulty) = match (
+        food_catalogs.get(&data_handles.food),
+        dude_catalogs.get(&data_handles.dude),
+        recipe_catalogs.get(&data_handles.recipe),
+        interaction_tables.get(&data_handles.interaction),
+        difficulty_catalogs.get(&data_handles.difficulty),
+    ) {
+        (Some(food), Some(dude), Some(recipe), Some(interaction), Some(difficulty)) => (
+            food.0.clone(),
+            dude.0.clone(),
+            recipe.0.clone(),
+            interaction.0.clone(),
+            difficulty.0.clone(),
+        ),
+        _ => return,
+    };
+    if let Err(err) = data::validate(&food, &dude, &recipe, &interaction, &difficulty) {
+        error!("{}, keeping the previous data", err);
+        return;
+    }
+
+    let mut atlases: Vec<Vec<String>> = vec![];
+    for food_info in food.iter() {
+        atlases.push(vec![food_info.sprite.clone()]);
+    }
+    for dude_info in dude.iter() {
+        let sprites = vec![
+            dude_info.sprite.clone(),
+            dude_info.sprite.clone() + "-gnam",
+            dude_info.sprite.clone() + "-puke",
+        ];
+        if !atlases.contains(&sprites) {
+            atlases.push(sprites);
+        }
+        for sound in ["-gnam", "-puke", "-yeah"] {
+            let name = dude_info.sprite.clone() + sound;
+            let path = "audio/".to_string() + &name + ".ogg";
+            audio_handles.handles.entry(name).or_insert_with(|| {
+                let handle: Handle<AudioSource> = asset_server.load(&path);
+                pending.handles.push(handle.id);
+                raw_handles.audio.push(handle.clone());
+                handle
+            });
+        }
+    }
+    atlases.retain(|atlas| !raw_handles.sprite_map.contains_key(&atlas[0]));
+    for atlas in atlases.iter() {
+        for name in atlas {
+            let handle: Handle<Image> =
+                asset_server.load(&("sprites/".to_string() + name + ".png"));
+            pending.handles.push(handle.id);
+            raw_handles.sprites.push(handle.clone());
+            raw_handles
+                .sprite_map
+                .insert(name.to_string(), handle.as_weak());
+        }
+    }
+    pending.atlases.extend(atlases);
+    pending.catalogs = Some((food, dude, recipe, interaction, difficulty));
+}
+
+pub fn apply_reload(
+    mut commands: Commands,
+    mut pending: ResMut<PendingReload>,
+    asset_server: Res<AssetServer>,
+    mut textures: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut atlas_handles: ResMut<AtlasHandles>,
+    info: Option<ResMut<Info>>,
+    mut food_query: Query<(Entity, &mut Food)>,
+    mut dude_query: Query<&mut Dude>,
+    mut kitchen: ResMut<Kitchen>,
+    mut restock_event: EventWriter<RestockShelfEvent>,
+) {
+    if pending.catalogs.is_none() {
+        return;
+    }
+    match asset_server.get_group_load_state(pending.handles.iter().cloned()) {
+        LoadState::Loaded => (),
+        LoadState::Failed => {
+            error!("the reloaded catalogs reference missing sprites or audio, keeping the previous data");
+            *pending = PendingReload::default();
+            return;
+        }
+        _ => return,
+    }
+    let mut info = info.unwrap();
+    let pending = std::mem::take(&mut *pending);
+    for atlas in pending.atlases {
+        let texture_atlas = build_atlas(&atlas, &asset_server, &mut textures);
+        atlas_handles
+            .handles
+            .insert(atlas[0].to_string(), texture_atlases.add(texture_atlas));
+        info.atlases.push(atlas);
+    }
+    let (food, dude, recipe, interaction, difficulty) = pending.catalogs.unwrap();
+    info.food = food;
+    info.dude = dude;
+    info.recipe = recipe;
+    info.interaction = interaction;
+    info.difficulty = difficulty;
+
+    for (ent, mut food) in &mut food_query {
+        if let Some(food_info) = info.food.iter().find(|f| f.sprite == food.info.sprite) {
+            food.info = food_info.clone();
+        } else if food.state == FoodState::Shelved {
+            commands.entity(ent).despawn();
+            restock_event.send(RestockShelfEvent { shelf: food.shelf });
+        }
+    }
+    for mut dude in &mut dude_query {
+        if let Some(dude_info) = info.dude.iter().find(|d| d.name == dude.info.name) {
+            dude.info = dude_info.clone();
+        }
+    }
+    for customer in kitchen.customers.iter_mut() {
+        if let Some(dude_info) = info.dude.iter().find(|d| d.name == customer.info.name) {
+            customer.info = dude_info.clone();
+        }
+    }
+    info!(
+        "reloaded {}, {}, {}, {} and {}",
+        data::FOOD_CATALOG,
+        data::DUDE_CATALOG,
+        data::RECIPE_CATALOG,
+        data::INTERACTION_TABLE,
+        data::DIFFICULTY_CATALOG
+    );
+}
+
+pub fn reload_sprites(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    raw_handles: Res<RawHandles>,
+    info: Option<Res<Info>>,
+    asset_server: Res<AssetServer>,
+    mut textures: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    atlas_handles: Res<AtlasHandles>,
+) {
+    let mut modified: Vec<&String> = vec![];
+    for event in image_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if let Some((name, _)) = raw_handles.sprite_map.iter().find(|(_, h)| *h == handle) {
+                modified.push(name);
+            }
+        }
+    }
+    if modified.is_empty() {
+        return;
+    }
+    let info = match info {
+        Some(info) => info,
+        None => return,
+    };
+    for atlas in info.atlases.iter() {
+        if !atlas.iter().any(|name| modified.contains(&name)) {
+            continue;
+        }
+        if let Some(atlas_handle) = atlas_handles.handles.get(&atlas[0]) {
+            let texture_atlas = build_atlas(atlas, &asset_server, &mut textures);
+            if let Some(old) = texture_atlases.get_mut(atlas_handle) {
+                let old = std::mem::replace(old, texture_atlas);
+                textures.remove(&old.texture);
+            }
+        }
+    }
+}
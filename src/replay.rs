@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::args;
+use crate::sim::Kitchen;
+use crate::{HandAction, MatchTimers};
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub lives: u32,
+    #[serde(default = "default_difficulty")]
+    pub difficulty: String,
+    pub actions: Vec<ReplayAction>,
+}
+
+fn default_difficulty() -> String {
+    "normal".to_string()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayAction {
+    pub round: u32,
+    pub step: u64,
+    pub action: HandAction,
+}
+
+impl Replay {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_args() -> Option<Replay> {
+        let path = args::get("replay")?;
+        let json = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("could not read replay {}: {}", path, err));
+        let replay = serde_json::from_str(&json)
+            .unwrap_or_else(|err| panic!("invalid replay {}: {}", path, err));
+        Some(replay)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_args() -> Option<Replay> {
+        if args::get("replay").is_some() {
+            warn!("replays can't be played on the web build");
+        }
+        None
+    }
+}
+
+pub struct Recorder {
+    path: String,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn from_args(seed: u64, lives: u32) -> Option<Recorder> {
+        let path = args::get("record")?;
+        if cfg!(target_arch = "wasm32") {
+            warn!("replays can't be recorded on the web build");
+            return None;
+        }
+        Some(Recorder {
+            path,
+            replay: Replay {
+                seed,
+                lives,
+                difficulty: default_difficulty(),
+                actions: vec![],
+            },
+        })
+    }
+
+    pub fn restart(&mut self, seed: u64, difficulty: &str) {
+        self.replay.seed = seed;
+        self.replay.difficulty = difficulty.to_string();
+        self.replay.actions.clear();
+    }
+}
+
+pub struct Playback {
+    pub difficulty: String,
+    actions: VecDeque<ReplayAction>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            difficulty: replay.difficulty,
+            actions: replay.actions.into(),
+        }
+    }
+
+    pub fn next_step(&self) -> Option<u64> {
+        self.actions.front().map(|next| next.step)
+    }
+}
+
+pub fn record_actions(
+    recorder: Option<ResMut<Recorder>>,
+    mut hand_action: EventReader<HandAction>,
+    kitchen: Res<Kitchen>,
+    match_timers: Res<MatchTimers>,
+) {
+    if let Some(mut recorder) = recorder {
+        for action in hand_action.iter() {
+            recorder.replay.actions.push(ReplayAction {
+                round: kitchen.round(),
+                step: match_timers.steps,
+                action: action.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_replay(recorder: Option<Res<Recorder>>) {
+    if let Some(recorder) = recorder {
+        let json = serde_json::to_string(&recorder.replay).unwrap();
+        if let Err(err) = std::fs::write(&recorder.path, json) {
+            error!("could not save replay {}: {}", recorder.path, err);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_replay() {}
+
+pub fn play_replay(
+    mut commands: Commands,
+    playback: Option<ResMut<Playback>>,
+    kitchen: Res<Kitchen>,
+    match_timers: Res<MatchTimers>,
+    mut hand_action: EventWriter<HandAction>,
+) {
+    let mut playback = match playback {
+        Some(playback) => playback,
+        None => return,
+    };
+    while let Some(next) = playback.actions.front() {
+        if next.step > match_timers.steps {
+            break;
+        }
+        if next.step < match_timers.steps || next.round != kitchen.round() {
+            warn!("replay drifted in round {}", next.round);
+        }
+        hand_action.send(next.action.clone());
+        playback.actions.pop_front();
+    }
+    if playback.actions.is_empty() {
+        info!("replay finished");
+        commands.remove_resource::<Playback>();
+    }
+}
@@ -0,0 +1,46 @@
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use crate::args;
+
+pub struct Rngs {
+    pub seed: u64,
+    pub customers: ChaCha8Rng,
+    pub palate: ChaCha8Rng,
+    pub shelf: ChaCha8Rng,
+    pub cooking: ChaCha8Rng,
+    pub visual: ChaCha8Rng,
+}
+
+impl Rngs {
+    pub fn new(seed: u64) -> Self {
+        Rngs {
+            seed,
+            customers: stream(seed, 0),
+            palate: stream(seed, 1),
+            shelf: stream(seed, 2),
+            cooking: stream(seed, 3),
+            visual: stream(seed, 4),
+        }
+    }
+
+    pub fn from_args() -> Self {
+        let seed = match args::get("seed").map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => seed,
+            Some(Err(err)) => panic!("invalid --seed: {}", err),
+            None => thread_rng().gen(),
+        };
+        Rngs::new(seed)
+    }
+
+    pub fn reseed(&mut self, keep: bool) {
+        let seed = if keep { self.seed } else { thread_rng().gen() };
+        *self = Rngs::new(seed);
+    }
+}
+
+fn stream(seed: u64, num: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(num);
+    rng
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::sim::Kitchen;
+use crate::{Dude, EatEvent, ObjectiveZone, ServedEvent};
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Save {
+    pub best: BestRun,
+    pub foods_eaten: u32,
+    pub foods_cooked: u32,
+    pub pukes: u32,
+    pub pan_smashes: u32,
+    pub dudes: BTreeMap<String, DudeStats>,
+    pub recipes: BTreeSet<String>,
+    #[serde(skip)]
+    pub new_best: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BestRun {
+    pub successes: u32,
+    pub peak_threshold: i32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DudeStats {
+    pub served: u32,
+    pub successes: u32,
+    pub pukes: u32,
+}
+
+impl Save {
+    pub fn load() -> Save {
+        match read("save") {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                warn!("ignoring the corrupted save: {}", err);
+                Save::default()
+            }),
+            None => Save::default(),
+        }
+    }
+
+    pub fn store(&self) {
+        if let Err(err) = write("save", &serde_json::to_string_pretty(self).unwrap()) {
+            error!("could not save: {}", err);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(name: &str) -> std::path::PathBuf {
+    let dir = if let Some(dir) = std::env::var_os("APPDATA") {
+        dir.into()
+    } else if let Some(dir) = std::env::var_os("XDG_DATA_HOME") {
+        dir.into()
+    } else if let Some(home) = std::env::var_os("HOME") {
+        std::path::PathBuf::from(home).join(".local").join("share")
+    } else {
+        std::path::PathBuf::from(".")
+    };
+    dir.join("perfect-chef").join(name.to_string() + ".json")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(path(name)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(name: &str, json: &str) -> Result<(), String> {
+    let path = path(name);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(&path, json).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read(name: &str) -> Option<String> {
+    storage()?
+        .get_item(&("perfect-chef-".to_string() + name))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(name: &str, json: &str) -> Result<(), String> {
+    storage()
+        .ok_or_else(|| "localStorage is not available".to_string())?
+        .set_item(&("perfect-chef-".to_string() + name), json)
+        .map_err(|err| format!("{:?}", err))
+}
+
+pub fn track_stats(
+    mut save: ResMut<Save>,
+    mut eat_event: EventReader<EatEvent>,
+    mut served_event: EventReader<ServedEvent>,
+    dude_query: Query<&Dude>,
+) {
+    for event in eat_event.iter() {
+        if event.to_zone == ObjectiveZone::Stomach {
+            save.foods_eaten += 1;
+        } else {
+            save.foods_cooked += 1;
+        }
+        if event.puke {
+            save.pukes += 1;
+            if let Ok(dude) = dude_query.get_single() {
+                save.dudes.entry(dude.info.name.clone()).or_default().pukes += 1;
+            }
+        }
+    }
+    for event in served_event.iter() {
+        save.pan_smashes += 1;
+        let stats = save.dudes.entry(event.dude.clone()).or_default();
+        stats.served += 1;
+        if event.success {
+            stats.successes += 1;
+        }
+        save.recipes.extend(event.recipes.iter().cloned());
+    }
+}
+
+pub fn store_stats(save: Res<Save>) {
+    save.store();
+}
+
+pub fn record_best(mut save: ResMut<Save>, kitchen: Res<Kitchen>) {
+    save.new_best = kitchen.score.successes > save.best.successes
+        || (kitchen.score.successes == save.best.successes
+            && kitchen.score.peak_threshold > save.best.peak_threshold);
+    if save.new_best {
+        save.best = BestRun {
+            successes: kitchen.score.successes,
+            peak_threshold: kitchen.score.peak_threshold,
+        };
+    }
+    save.store();
+}
@@ -0,0 +1,1077 @@
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Add;
+
+const OVEREAT_PENALTY: f32 = 5.0;
+
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug, Deserialize)]
+pub enum Taste {
+    #[default]
+    Sweet,
+    Sour,
+    Salty,
+    Bitter,
+    Savory,
+    Spicy,
+    Cool,
+    Dry,
+}
+
+impl Taste {
+    pub fn from_u32(value: u32) -> Taste {
+        match value {
+            0 => Taste::Sweet,
+            1 => Taste::Sour,
+            2 => Taste::Salty,
+            3 => Taste::Bitter,
+            4 => Taste::Savory,
+            5 => Taste::Spicy,
+            6 => Taste::Cool,
+            7 => Taste::Dry,
+            _ => panic!("Unknown value: {}", value),
+        }
+    }
+    pub fn digest_odds(&self) -> u32 {
+        match self {
+            Taste::Sweet => 15,
+            Taste::Sour => 20,
+            Taste::Salty => 30,
+            Taste::Bitter => 25,
+            Taste::Savory => 35,
+            Taste::Spicy => 20,
+            Taste::Cool => 10,
+            Taste::Dry => 40,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Taste::Sweet => "Sweet",
+            Taste::Sour => "Sour",
+            Taste::Salty => "Salty",
+            Taste::Bitter => "Bitter",
+            Taste::Savory => "Savory",
+            Taste::Spicy => "Spicy",
+            Taste::Cool => "Cool",
+            Taste::Dry => "Dry",
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Flavor(pub HashMap<Taste, f32>);
+
+impl Add for Flavor {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let mut sum = Self::default();
+        for i in 0..8 {
+            let taste = Taste::from_u32(i);
+            let value = self.0.get(&taste).unwrap_or(&0.0) + other.0.get(&taste).unwrap_or(&0.0);
+            sum.0.insert(taste, value);
+        }
+        sum
+    }
+}
+
+/// How much a customer values each taste. A taste missing from the map is
+/// Normal (1.0) for scoring, preference rows and puking alike.
+#[derive(Clone, Default, Debug)]
+pub struct Palate(HashMap<Taste, f32>);
+
+impl Palate {
+    pub fn gen(rng: &mut impl Rng, odds: &PalateOdds) -> Self {
+        let mut ret = Self::default();
+        let total = odds.resist + odds.dislike + odds.like + odds.normal;
+        for i in 0..8 {
+            let r = rng.gen_range(0..total);
+            let preference = if r < odds.resist {
+                Preference::Resist
+            } else if r < odds.resist + odds.dislike {
+                Preference::Dislike
+            } else if r < odds.resist + odds.dislike + odds.like {
+                Preference::Like
+            } else {
+                Preference::Normal
+            };
+            ret.set(Taste::from_u32(i), preference);
+        }
+        ret
+    }
+
+    pub fn set(&mut self, taste: Taste, preference: Preference) {
+        self.0.insert(taste, preference.value());
+    }
+
+    pub fn value(&self, taste: &Taste) -> f32 {
+        self.0.get(taste).copied().unwrap_or(1.0)
+    }
+
+    pub fn preference(&self, taste: &Taste) -> Preference {
+        Preference::from_f32(self.value(taste))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct PalateOdds {
+    pub resist: u32,
+    pub dislike: u32,
+    pub like: u32,
+    pub normal: u32,
+}
+
+impl Default for PalateOdds {
+    fn default() -> Self {
+        PalateOdds {
+            resist: 10,
+            dislike: 10,
+            like: 20,
+            normal: 60,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum Preference {
+    Like,
+    Dislike,
+    Resist,
+    Normal,
+}
+
+impl Preference {
+    pub fn value(&self) -> f32 {
+        match self {
+            Preference::Like => 2.0,
+            Preference::Dislike => -1.0,
+            Preference::Resist => 0.0,
+            Preference::Normal => 1.0,
+        }
+    }
+
+    pub fn from_f32(n: f32) -> Self {
+        if n < 0.0 {
+            Preference::Dislike
+        } else if n > 1.0 {
+            Preference::Like
+        } else if n > 0.0 {
+            Preference::Normal
+        } else {
+            Preference::Resist
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct FoodInfo {
+    pub sprite: String,
+    pub flavor: Flavor,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct DudeInfo {
+    pub name: String,
+    pub sprite: String,
+    #[serde(default)]
+    pub palate: PalateOdds,
+    #[serde(default)]
+    pub likes: Vec<Taste>,
+    #[serde(default)]
+    pub dislikes: Vec<Taste>,
+    #[serde(default = "default_puke_tolerance")]
+    pub puke_tolerance: u32,
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    #[serde(default = "default_hidden")]
+    pub hidden: usize,
+    #[serde(default = "unit_multiplier")]
+    pub score_multiplier: f32,
+}
+
+impl DudeInfo {
+    pub fn gen_palate(&self, rng: &mut impl Rng) -> Palate {
+        let mut palate = Palate::gen(rng, &self.palate);
+        for taste in self.likes.iter() {
+            palate.set(taste.clone(), Preference::Like);
+        }
+        for taste in self.dislikes.iter() {
+            palate.set(taste.clone(), Preference::Dislike);
+        }
+        palate
+    }
+
+    pub fn gen_hidden(&self, rng: &mut impl Rng) -> Vec<Taste> {
+        let tastes: Vec<Taste> = (0..8).map(Taste::from_u32).collect();
+        tastes.choose_multiple(rng, self.hidden).cloned().collect()
+    }
+}
+
+fn default_puke_tolerance() -> u32 {
+    3
+}
+
+fn default_capacity() -> usize {
+    12
+}
+
+fn default_hidden() -> usize {
+    3
+}
+
+#[derive(Clone, Deserialize)]
+pub struct RecipeInfo {
+    pub name: String,
+    pub ingredients: Vec<String>,
+    #[serde(default)]
+    pub bonus: f32,
+    #[serde(default = "unit_multiplier")]
+    pub multiplier: f32,
+}
+
+fn unit_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum Effect {
+    Cancel,
+    Amplify,
+    Neutralize,
+}
+
+impl Effect {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Effect::Cancel => "cancels",
+            Effect::Amplify => "amplifies",
+            Effect::Neutralize => "neutralizes",
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Interaction {
+    pub taste: Taste,
+    pub with: Taste,
+    pub effect: Effect,
+    #[serde(default = "default_chance")]
+    pub chance: f64,
+}
+
+fn default_chance() -> f64 {
+    0.2
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub id: u32,
+    pub taste: Taste,
+}
+
+pub struct Bite {
+    pub tokens: Vec<Token>,
+    pub puke: bool,
+    pub overate: bool,
+}
+
+#[derive(Clone)]
+pub struct Customer {
+    pub id: u32,
+    pub info: DudeInfo,
+    pub palate: Palate,
+    pub hidden: Vec<Taste>,
+    pub stomach: Vec<Token>,
+    pub patience: f32,
+    pub pukes: u32,
+    pub penalty: f32,
+}
+
+pub enum TickEvent {
+    Digested(u32),
+    Left(Box<Customer>),
+    Duplicated {
+        from: u32,
+        token: Token,
+    },
+    Reacted {
+        interaction: Interaction,
+        at: u32,
+        removed: Vec<u32>,
+    },
+}
+
+pub struct Tally {
+    pub sum: f32,
+    pub threshold: i32,
+    pub success: bool,
+    pub recipes: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Score {
+    pub served: u32,
+    pub successes: u32,
+    pub losses: u32,
+    pub peak_threshold: i32,
+}
+
+#[derive(Clone)]
+pub struct RunRules {
+    pub lives: u32,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct DifficultyProfile {
+    pub name: String,
+    pub start_threshold: i32,
+    pub success_delta: i32,
+    pub failure_delta: i32,
+    pub round_secs: f32,
+    #[serde(default = "default_reward_secs")]
+    pub reward_secs: f32,
+    pub cook_interval: f32,
+    pub duplication_chance: f64,
+    pub shelves: usize,
+    #[serde(default)]
+    pub adaptive: Option<Adaptive>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Adaptive {
+    pub window: usize,
+    #[serde(default = "default_min_rounds")]
+    pub min_rounds: usize,
+    pub target_win_rate: f32,
+    pub threshold_step: f32,
+    #[serde(default = "default_min_threshold")]
+    pub min_threshold: i32,
+    pub duplication_step: f64,
+}
+
+fn default_min_rounds() -> usize {
+    3
+}
+
+fn default_min_threshold() -> i32 {
+    1
+}
+
+fn default_reward_secs() -> f32 {
+    5.0
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        DifficultyProfile {
+            name: "normal".to_string(),
+            start_threshold: 30,
+            success_delta: 10,
+            failure_delta: -3,
+            round_secs: 10.0,
+            reward_secs: default_reward_secs(),
+            cook_interval: 1.0,
+            duplication_chance: 0.04,
+            shelves: 3,
+            adaptive: None,
+        }
+    }
+}
+
+pub struct Difficulty {
+    pub threshold: i32,
+    pub duplication_chance: f64,
+    pub margins: VecDeque<f32>,
+}
+
+pub struct Kitchen {
+    pub rules: RunRules,
+    pub customers: Vec<Customer>,
+    pub selected: Option<u32>,
+    pub pan: Vec<Token>,
+    pub cooked: Vec<String>,
+    pub score: Score,
+    pub profile: DifficultyProfile,
+    pub difficulty: Difficulty,
+    pub cook_interval: f32,
+    pub ticks: u64,
+    cook_elapsed: f32,
+    next_id: u32,
+    next_customer: u32,
+}
+
+impl Kitchen {
+    pub fn new(profile: DifficultyProfile, rules: RunRules) -> Self {
+        let threshold = profile.start_threshold;
+        Kitchen {
+            rules,
+            customers: vec![],
+            selected: None,
+            pan: vec![],
+            cooked: vec![],
+            score: Score {
+                peak_threshold: threshold,
+                ..Score::default()
+            },
+            difficulty: Difficulty {
+                threshold,
+                duplication_chance: profile.duplication_chance,
+                margins: VecDeque::new(),
+            },
+            cook_interval: profile.cook_interval,
+            profile,
+            ticks: 0,
+            cook_elapsed: 0.0,
+            next_id: 0,
+            next_customer: 0,
+        }
+    }
+
+    pub fn arrive(
+        &mut self,
+        info: DudeInfo,
+        palate: Palate,
+        hidden: Vec<Taste>,
+        patience: f32,
+    ) -> u32 {
+        self.next_customer += 1;
+        let id = self.next_customer;
+        self.customers.push(Customer {
+            id,
+            info,
+            palate,
+            hidden,
+            stomach: vec![],
+            patience,
+            pukes: 0,
+            penalty: 0.0,
+        });
+        if self.selected.is_none() {
+            self.selected = Some(id);
+        }
+        id
+    }
+
+    pub fn select(&mut self, id: u32) {
+        if self.customers.iter().any(|customer| customer.id == id) {
+            self.selected = Some(id);
+        }
+    }
+
+    pub fn next_in_line(&self) -> Option<u32> {
+        let index = self
+            .customers
+            .iter()
+            .position(|customer| Some(customer.id) == self.selected)?;
+        let next = &self.customers[(index + 1) % self.customers.len()];
+        Some(next.id)
+    }
+
+    pub fn customer(&self) -> Option<&Customer> {
+        let id = self.selected?;
+        self.customers.iter().find(|customer| customer.id == id)
+    }
+
+    pub fn stomach(&self) -> &[Token] {
+        match self.customer() {
+            Some(customer) => &customer.stomach,
+            None => &[],
+        }
+    }
+
+    pub fn eat(&mut self, food: &FoodInfo) -> Bite {
+        let mut bite = self.bite(&food.flavor);
+        if let Some(customer) = self.customer_mut() {
+            customer
+                .hidden
+                .retain(|taste| *food.flavor.0.get(taste).unwrap_or(&0.0) == 0.0);
+            if customer.stomach.len() + bite.tokens.len() > customer.info.capacity {
+                customer.stomach.clear();
+                customer.penalty += OVEREAT_PENALTY;
+                bite.tokens.clear();
+                bite.puke = true;
+                bite.overate = true;
+            } else {
+                customer.stomach.extend(bite.tokens.iter().cloned());
+            }
+            if bite.puke {
+                customer.pukes += 1;
+            }
+        }
+        bite
+    }
+
+    pub fn cook(&mut self, food: &FoodInfo) -> Bite {
+        let bite = self.bite(&food.flavor);
+        self.pan.extend(bite.tokens.iter().cloned());
+        self.cooked.push(food.sprite.clone());
+        bite
+    }
+
+    pub fn tick(
+        &mut self,
+        dt: f32,
+        interactions: &[Interaction],
+        rng: &mut impl Rng,
+    ) -> Vec<TickEvent> {
+        let mut events = vec![];
+        if self.cook_interval <= 0.0 {
+            return events;
+        }
+        self.cook_elapsed += dt;
+        while self.cook_elapsed >= self.cook_interval {
+            self.cook_elapsed -= self.cook_interval;
+            self.ticks += 1;
+            self.cook_step(interactions, rng, &mut events);
+        }
+        events
+    }
+
+    pub fn round(&self) -> u32 {
+        self.score.successes + self.score.losses
+    }
+
+    pub fn lives_left(&self) -> Option<u32> {
+        if self.rules.lives == 0 {
+            None
+        } else {
+            Some(self.rules.lives.saturating_sub(self.score.losses))
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lives_left() == Some(0)
+    }
+
+    pub fn smash(&mut self, recipes: &[RecipeInfo]) -> Tally {
+        let sum = self.total(recipes, |taste| self.weigh(taste));
+        let recipes = self
+            .completed(recipes)
+            .iter()
+            .map(|recipe| recipe.name.clone())
+            .collect();
+        if let Some(id) = self.selected {
+            self.leave(id);
+        }
+        self.pan.clear();
+        self.cooked.clear();
+        self.score.served += 1;
+
+        let threshold = self.difficulty.threshold;
+        let success = sum as i32 >= threshold;
+        if success {
+            self.score.successes += 1;
+            self.difficulty.threshold += self.profile.success_delta;
+            self.score.peak_threshold = self.score.peak_threshold.max(self.difficulty.threshold);
+        } else {
+            self.fail();
+        }
+        self.adapt(sum - threshold as f32);
+        Tally {
+            sum,
+            threshold,
+            success,
+            recipes,
+        }
+    }
+
+    pub fn weigh(&self, taste: &Taste) -> f32 {
+        match self.customer() {
+            Some(customer) => customer.palate.value(taste) * customer.info.score_multiplier,
+            None => 1.0,
+        }
+    }
+
+    pub fn preview(&self, flavor: &Flavor) -> f32 {
+        let mut sum = 0.0;
+        for i in 0..8 {
+            let taste = Taste::from_u32(i);
+            let value = *flavor.0.get(&taste).unwrap_or(&0.0);
+            sum += (value as u32) as f32 * self.guess(&taste);
+        }
+        sum
+    }
+
+    pub fn completed<'a>(&self, recipes: &'a [RecipeInfo]) -> Vec<&'a RecipeInfo> {
+        recipes
+            .iter()
+            .filter(|recipe| {
+                recipe
+                    .ingredients
+                    .iter()
+                    .all(|ingredient| self.cooked.contains(ingredient))
+            })
+            .collect()
+    }
+
+    pub fn projected(&self, recipes: &[RecipeInfo]) -> f32 {
+        self.total(recipes, |taste| self.guess(taste))
+    }
+
+    fn total(&self, recipes: &[RecipeInfo], value: impl Fn(&Taste) -> f32) -> f32 {
+        let mut sum: f32 = self
+            .stomach()
+            .iter()
+            .chain(self.pan.iter())
+            .map(|token| value(&token.taste))
+            .sum();
+        let completed = self.completed(recipes);
+        for recipe in completed.iter() {
+            sum += recipe.bonus;
+        }
+        for recipe in completed.iter() {
+            sum *= recipe.multiplier;
+        }
+        if let Some(customer) = self.customer() {
+            sum -= customer.penalty;
+        }
+        sum
+    }
+
+    pub fn next_tick_range(&self, interactions: &[Interaction]) -> (f32, f32) {
+        let mut changes: Vec<f32> = self
+            .stomach()
+            .iter()
+            .map(|token| -self.guess(&token.taste))
+            .collect();
+        changes.extend(
+            self.pan
+                .iter()
+                .filter(|token| self.has_twin(token) && !self.resists_known(&token.taste))
+                .map(|token| self.guess(&token.taste)),
+        );
+        let in_pan = |taste: &Taste| self.pan.iter().any(|token| token.taste == *taste);
+        for interaction in interactions {
+            if interaction.chance <= 0.0
+                || !in_pan(&interaction.taste)
+                || !in_pan(&interaction.with)
+            {
+                continue;
+            }
+            changes.push(match interaction.effect {
+                Effect::Cancel => -self.guess(&interaction.taste) - self.guess(&interaction.with),
+                Effect::Neutralize => -self.guess(&interaction.with),
+                Effect::Amplify if self.resists_known(&interaction.with) => continue,
+                Effect::Amplify => self.guess(&interaction.with),
+            });
+        }
+        let worst = changes.iter().filter(|change| **change < 0.0).sum();
+        let best = changes.iter().filter(|change| **change > 0.0).sum();
+        (worst, best)
+    }
+
+    pub fn would_overeat(&self, flavor: &Flavor) -> bool {
+        let tokens: u32 = flavor.0.values().map(|value| *value as u32).sum();
+        match self.customer() {
+            Some(customer) => customer.stomach.len() + tokens as usize > customer.info.capacity,
+            None => false,
+        }
+    }
+
+    pub fn would_puke(&self, flavor: &Flavor) -> bool {
+        self.dislikes(flavor, false)
+    }
+
+    pub fn resists(&self, taste: &Taste) -> bool {
+        match self.customer() {
+            Some(customer) => customer.palate.preference(taste) == Preference::Resist,
+            None => false,
+        }
+    }
+
+    pub fn is_hidden(&self, taste: &Taste) -> bool {
+        match self.customer() {
+            Some(customer) => customer.hidden.contains(taste),
+            None => false,
+        }
+    }
+
+    fn guess(&self, taste: &Taste) -> f32 {
+        match self.customer() {
+            Some(customer) if customer.hidden.contains(taste) => customer.info.score_multiplier,
+            _ => self.weigh(taste),
+        }
+    }
+
+    fn resists_known(&self, taste: &Taste) -> bool {
+        !self.is_hidden(taste) && self.resists(taste)
+    }
+
+    fn dislikes(&self, flavor: &Flavor, hidden: bool) -> bool {
+        (0..8).map(Taste::from_u32).any(|taste| {
+            let value = *flavor.0.get(&taste).unwrap_or(&0.0);
+            let preference = match self.customer() {
+                Some(customer) => customer.palate.preference(&taste),
+                None => Preference::Normal,
+            };
+            (hidden || !self.is_hidden(&taste)) && preference == Preference::Dislike && value > 0.0
+        })
+    }
+
+    fn bite(&mut self, flavor: &Flavor) -> Bite {
+        let mut tokens = vec![];
+        let puke = self.dislikes(flavor, true);
+        for i in 0..8 {
+            let taste = Taste::from_u32(i);
+            let value = *flavor.0.get(&taste).unwrap_or(&0.0);
+            for _ in 0..(value as u32) {
+                tokens.push(self.token(taste.clone()));
+            }
+        }
+        Bite {
+            tokens,
+            puke,
+            overate: false,
+        }
+    }
+
+    fn token(&mut self, taste: Taste) -> Token {
+        self.next_id += 1;
+        Token {
+            id: self.next_id,
+            taste,
+        }
+    }
+
+    fn customer_mut(&mut self) -> Option<&mut Customer> {
+        let id = self.selected?;
+        self.customers.iter_mut().find(|customer| customer.id == id)
+    }
+
+    fn leave(&mut self, id: u32) -> Option<Customer> {
+        let index = self
+            .customers
+            .iter()
+            .position(|customer| customer.id == id)?;
+        let customer = self.customers.remove(index);
+        if self.selected == Some(id) {
+            self.selected = self.customers.first().map(|customer| customer.id);
+        }
+        Some(customer)
+    }
+
+    fn adapt(&mut self, margin: f32) {
+        let adaptive = match &self.profile.adaptive {
+            Some(adaptive) => adaptive,
+            None => return,
+        };
+        let difficulty = &mut self.difficulty;
+        difficulty.margins.push_back(margin);
+        while difficulty.margins.len() > adaptive.window {
+            difficulty.margins.pop_front();
+        }
+        if difficulty.margins.len() >= adaptive.min_rounds {
+            let rounds = difficulty.margins.len() as f32;
+            let wins = difficulty.margins.iter().filter(|m| **m >= 0.0).count() as f32;
+            let mean = difficulty.margins.iter().sum::<f32>() / rounds;
+            let error = wins / rounds - adaptive.target_win_rate;
+            difficulty.threshold += (error * (mean.abs() + adaptive.threshold_step)).round() as i32;
+            difficulty.duplication_chance = (difficulty.duplication_chance
+                - error as f64 * adaptive.duplication_step)
+                .clamp(0.0, 1.0);
+        }
+        difficulty.threshold = difficulty.threshold.max(adaptive.min_threshold);
+        self.score.peak_threshold = self.score.peak_threshold.max(difficulty.threshold);
+    }
+
+    fn fail(&mut self) {
+        self.score.losses += 1;
+        self.difficulty.threshold += self.profile.failure_delta;
+    }
+
+    fn can_duplicate(&self, token: &Token) -> bool {
+        !self.resists(&token.taste) && self.has_twin(token)
+    }
+
+    fn has_twin(&self, token: &Token) -> bool {
+        self.stomach()
+            .iter()
+            .chain(self.pan.iter())
+            .any(|other| other.id != token.id && other.taste == token.taste)
+    }
+
+    fn cook_step(
+        &mut self,
+        interactions: &[Interaction],
+        rng: &mut impl Rng,
+        events: &mut Vec<TickEvent>,
+    ) {
+        let candidates: Vec<bool> = self
+            .pan
+            .iter()
+            .map(|token| self.can_duplicate(token))
+            .collect();
+
+        for customer in self.customers.iter_mut() {
+            customer.patience -= self.cook_interval;
+        }
+        let gone: Vec<u32> = self
+            .customers
+            .iter()
+            .filter(|customer| {
+                customer.patience <= 0.0 || customer.pukes > customer.info.puke_tolerance
+            })
+            .map(|customer| customer.id)
+            .collect();
+        for id in gone {
+            if let Some(customer) = self.leave(id) {
+                self.fail();
+                events.push(TickEvent::Left(Box::new(customer)));
+            }
+        }
+
+        let mut digested = vec![];
+        for token in self.stomach().iter() {
+            if rng.gen_ratio(1, token.taste.digest_odds()) {
+                digested.push(token.id);
+            }
+        }
+        if let Some(customer) = self.customer_mut() {
+            customer
+                .stomach
+                .retain(|token| !digested.contains(&token.id));
+        }
+        events.extend(digested.into_iter().map(TickEvent::Digested));
+
+        let mut duplicated = vec![];
+        for (token, candidate) in self.pan.iter().zip(candidates) {
+            if candidate && rng.gen_bool(self.difficulty.duplication_chance) {
+                duplicated.push((token.id, token.taste.clone()));
+            }
+        }
+        for (from, taste) in duplicated {
+            let token = self.token(taste);
+            self.pan.push(token.clone());
+            events.push(TickEvent::Duplicated { from, token });
+        }
+
+        for interaction in interactions {
+            let find = |taste: &Taste| {
+                self.pan
+                    .iter()
+                    .find(|token| token.taste == *taste)
+                    .map(|token| token.id)
+            };
+            let (at, target) = match (find(&interaction.taste), find(&interaction.with)) {
+                (Some(at), Some(target)) => (at, target),
+                _ => continue,
+            };
+            if interaction.effect == Effect::Amplify && self.resists(&interaction.with) {
+                continue;
+            }
+            if !rng.gen_bool(interaction.chance) {
+                continue;
+            }
+            let removed = match interaction.effect {
+                Effect::Cancel => vec![at, target],
+                Effect::Neutralize => vec![target],
+                Effect::Amplify => {
+                    let token = self.token(interaction.with.clone());
+                    self.pan.push(token.clone());
+                    events.push(TickEvent::Duplicated {
+                        from: target,
+                        token,
+                    });
+                    vec![]
+                }
+            };
+            self.pan.retain(|token| !removed.contains(&token.id));
+            events.push(TickEvent::Reacted {
+                interaction: interaction.clone(),
+                at,
+                removed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha8Rng;
+
+    fn dude() -> DudeInfo {
+        DudeInfo {
+            name: "dude".to_string(),
+            sprite: "dude".to_string(),
+            palate: PalateOdds::default(),
+            likes: vec![],
+            dislikes: vec![],
+            puke_tolerance: default_puke_tolerance(),
+            capacity: default_capacity(),
+            hidden: 0,
+            score_multiplier: 1.0,
+        }
+    }
+
+    fn food(taste: Taste, amount: f32) -> FoodInfo {
+        FoodInfo {
+            sprite: taste.as_str().to_string(),
+            flavor: Flavor(HashMap::from([(taste, amount)])),
+        }
+    }
+
+    fn kitchen(palate: Palate) -> Kitchen {
+        let mut kitchen = Kitchen::new(DifficultyProfile::default(), RunRules { lives: 3 });
+        kitchen.arrive(dude(), palate, vec![], 100.0);
+        kitchen
+    }
+
+    #[test]
+    fn missing_tastes_read_as_normal() {
+        let mut palate = Palate::default();
+        palate.set(Taste::Sour, Preference::Dislike);
+        assert_eq!(palate.value(&Taste::Sweet), 1.0);
+        assert!(palate.preference(&Taste::Sweet) == Preference::Normal);
+
+        let mut kitchen = kitchen(palate);
+        assert!(!kitchen.resists(&Taste::Sweet));
+        assert!(!kitchen.eat(&food(Taste::Sweet, 1.0)).puke);
+        kitchen.cook(&food(Taste::Sweet, 2.0));
+        assert_eq!(kitchen.smash(&[]).sum, 3.0);
+    }
+
+    #[test]
+    fn preferences_agree_everywhere() {
+        for preference in [
+            Preference::Like,
+            Preference::Dislike,
+            Preference::Resist,
+            Preference::Normal,
+        ] {
+            let mut palate = Palate::default();
+            palate.set(Taste::Sweet, preference.clone());
+            assert_eq!(palate.value(&Taste::Sweet), preference.value());
+            assert!(palate.preference(&Taste::Sweet) == preference);
+
+            let mut kitchen = kitchen(palate);
+            assert_eq!(
+                kitchen.resists(&Taste::Sweet),
+                preference == Preference::Resist
+            );
+            assert_eq!(
+                kitchen.eat(&food(Taste::Sweet, 1.0)).puke,
+                preference == Preference::Dislike
+            );
+            kitchen.cook(&food(Taste::Sweet, 2.0));
+            assert_eq!(kitchen.smash(&[]).sum, 3.0 * preference.value());
+        }
+    }
+
+    #[test]
+    fn smash_moves_the_threshold() {
+        let mut kitchen = kitchen(Palate::default());
+        kitchen.difficulty.threshold = 3;
+        kitchen.cook(&food(Taste::Sweet, 3.0));
+        let tally = kitchen.smash(&[]);
+        assert!(tally.success);
+        assert_eq!(tally.threshold, 3);
+        assert_eq!(kitchen.difficulty.threshold, 13);
+        assert_eq!(kitchen.score.peak_threshold, 30);
+
+        kitchen.arrive(dude(), Palate::default(), vec![], 100.0);
+        let tally = kitchen.smash(&[]);
+        assert!(!tally.success);
+        assert_eq!(kitchen.difficulty.threshold, 10);
+        assert_eq!(kitchen.score.successes, 1);
+        assert_eq!(kitchen.score.losses, 1);
+        assert_eq!(kitchen.score.served, 2);
+    }
+
+    #[test]
+    fn disliked_taste_pukes() {
+        let mut palate = Palate::default();
+        palate.set(Taste::Bitter, Preference::Dislike);
+        let mut kitchen = kitchen(palate);
+        assert!(kitchen.would_puke(&food(Taste::Bitter, 1.0).flavor));
+        assert!(kitchen.eat(&food(Taste::Bitter, 1.0)).puke);
+        assert!(!kitchen.eat(&food(Taste::Sweet, 1.0)).puke);
+        assert_eq!(kitchen.customer().unwrap().pukes, 1);
+    }
+
+    #[test]
+    fn hidden_tastes_project_as_normal_until_eaten() {
+        let mut palate = Palate::default();
+        palate.set(Taste::Sweet, Preference::Like);
+        let mut kitchen = Kitchen::new(DifficultyProfile::default(), RunRules { lives: 3 });
+        kitchen.arrive(dude(), palate, vec![Taste::Sweet], 100.0);
+        kitchen.cook(&food(Taste::Sweet, 2.0));
+        assert!(kitchen.is_hidden(&Taste::Sweet));
+        assert_eq!(kitchen.preview(&food(Taste::Sweet, 1.0).flavor), 1.0);
+        assert_eq!(kitchen.projected(&[]), 2.0);
+        assert_eq!(kitchen.next_tick_range(&[]), (0.0, 2.0));
+
+        kitchen.eat(&food(Taste::Sweet, 1.0));
+        assert!(!kitchen.is_hidden(&Taste::Sweet));
+        assert_eq!(kitchen.preview(&food(Taste::Sweet, 1.0).flavor), 2.0);
+        assert_eq!(kitchen.projected(&[]), 6.0);
+        assert_eq!(kitchen.next_tick_range(&[]), (-2.0, 4.0));
+    }
+
+    #[test]
+    fn tick_range_counts_interactions() {
+        let mut kitchen = kitchen(Palate::default());
+        kitchen.cook(&food(Taste::Sweet, 1.0));
+        kitchen.cook(&food(Taste::Sour, 1.0));
+        let interaction = |effect| Interaction {
+            taste: Taste::Sweet,
+            with: Taste::Sour,
+            effect,
+            chance: 1.0,
+        };
+        assert_eq!(kitchen.next_tick_range(&[]), (0.0, 0.0));
+        assert_eq!(
+            kitchen.next_tick_range(&[interaction(Effect::Cancel)]),
+            (-2.0, 0.0)
+        );
+        assert_eq!(
+            kitchen.next_tick_range(&[interaction(Effect::Neutralize)]),
+            (-1.0, 0.0)
+        );
+        assert_eq!(
+            kitchen.next_tick_range(&[interaction(Effect::Amplify)]),
+            (0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn adaptive_goal_waits_and_has_a_floor() {
+        let profile = DifficultyProfile {
+            success_delta: 0,
+            failure_delta: 0,
+            adaptive: Some(Adaptive {
+                window: 5,
+                min_rounds: 3,
+                target_win_rate: 0.6,
+                threshold_step: 4.0,
+                min_threshold: 10,
+                duplication_step: 0.02,
+            }),
+            ..DifficultyProfile::default()
+        };
+        let mut kitchen = Kitchen::new(profile, RunRules { lives: 0 });
+        let mut goals = vec![];
+        for _ in 0..6 {
+            kitchen.arrive(dude(), Palate::default(), vec![], 100.0);
+            kitchen.smash(&[]);
+            goals.push(kitchen.difficulty.threshold);
+        }
+        assert_eq!(goals[..2], [30, 30]);
+        assert!(goals[2] < 30);
+        assert!(goals.iter().all(|goal| *goal >= 10));
+        assert_eq!(goals[5], 10);
+    }
+
+    #[test]
+    fn tick_steps_every_interval() {
+        let mut kitchen = kitchen(Palate::default());
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        kitchen.tick(3.5, &[], &mut rng);
+        assert_eq!(kitchen.ticks, 3);
+        assert_eq!(kitchen.customer().unwrap().patience, 97.0);
+        kitchen.tick(0.5, &[], &mut rng);
+        assert_eq!(kitchen.ticks, 4);
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::sim::{Kitchen, Taste};
+use crate::{AtlasHandles, Food, FoodState, Hand, MousePos};
+
+const ROW_HEIGHT: f32 = 48.0;
+const WIDTH: f32 = 160.0;
+
+#[derive(Component)]
+pub struct Tooltip {
+    target: Entity,
+    preview: f32,
+}
+
+pub fn update_tooltip(
+    mut commands: Commands,
+    hand: Res<Hand>,
+    mousepos: Res<MousePos>,
+    kitchen: Res<Kitchen>,
+    asset_server: Res<AssetServer>,
+    atlas_handles: Res<AtlasHandles>,
+    food_query: Query<(Entity, &Food, &Transform), Without<Tooltip>>,
+    mut tooltip_query: Query<(Entity, &Tooltip, &mut Transform)>,
+) {
+    let hovered = food_query
+        .iter()
+        .find(|(_, food, tr)| {
+            food.state == FoodState::Shelved
+                && mousepos.world.distance_squared(tr.translation.truncate()) < 100.0 * 100.0
+        })
+        .map(|(ent, _, _)| ent);
+    let target = hand
+        .holding
+        .or(hovered)
+        .and_then(|ent| food_query.get(ent).ok());
+    let (target, food, food_tr) = match target {
+        Some(target) => target,
+        None => {
+            for (ent, _, _) in &tooltip_query {
+                commands.entity(ent).despawn_recursive();
+            }
+            return;
+        }
+    };
+
+    let preview = kitchen.preview(&food.info.flavor);
+    let translation = Vec3::new(
+        food_tr.translation.x + 96.0,
+        food_tr.translation.y + 64.0,
+        8.0,
+    );
+    if let Ok((ent, tooltip, mut tr)) = tooltip_query.get_single_mut() {
+        if tooltip.target == target && tooltip.preview == preview {
+            tr.translation = translation;
+            return;
+        }
+        commands.entity(ent).despawn_recursive();
+    }
+
+    let tastes: Vec<(Taste, u32)> = (0..8)
+        .map(Taste::from_u32)
+        .map(|taste| {
+            let value = *food.info.flavor.0.get(&taste).unwrap_or(&0.0) as u32;
+            (taste, value)
+        })
+        .filter(|(_, value)| *value > 0)
+        .collect();
+    let height = (tastes.len() + 1) as f32 * ROW_HEIGHT + 16.0;
+
+    let font = asset_server.load("fonts/SztyletBd.ttf");
+    let style = TextStyle {
+        font,
+        font_size: 40.0,
+        color: Color::BLACK,
+    };
+    let alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Left,
+    };
+    commands
+        .spawn_bundle(SpatialBundle {
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(Tooltip { target, preview })
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.85),
+                    custom_size: Some(Vec2::new(WIDTH, height)),
+                    anchor: Anchor::TopLeft,
+                    ..default()
+                },
+                ..default()
+            });
+            for (i, (taste, value)) in tastes.iter().enumerate() {
+                let y = -(i as f32 + 0.5) * ROW_HEIGHT - 8.0;
+                parent.spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite::new(0),
+                    texture_atlas: atlas_handles.handles.get(taste.as_str()).unwrap().clone(),
+                    transform: Transform {
+                        translation: Vec3::new(32.0, y, 0.1),
+                        scale: Vec3::splat(0.4),
+                        ..default()
+                    },
+                    ..default()
+                });
+                let label = if kitchen.is_hidden(taste) {
+                    format!("x{} ?", value)
+                } else {
+                    format!("x{}", value)
+                };
+                parent.spawn_bundle(Text2dBundle {
+                    text: Text::from_section(label, style.clone()).with_alignment(alignment),
+                    transform: Transform::from_xyz(64.0, y, 0.1),
+                    ..default()
+                });
+            }
+            let (label, color) = if kitchen.would_overeat(&food.info.flavor) {
+                (format!("{:+} overeat!", preview), Color::RED)
+            } else if kitchen.would_puke(&food.info.flavor) {
+                (format!("{:+} puke!", preview), Color::RED)
+            } else if preview > 0.0 {
+                (format!("{:+}", preview), Color::DARK_GREEN)
+            } else {
+                (format!("{:+}", preview), Color::BLACK)
+            };
+            parent.spawn_bundle(Text2dBundle {
+                text: Text::from_section(label, TextStyle { color, ..style })
+                    .with_alignment(alignment),
+                transform: Transform::from_xyz(
+                    16.0,
+                    -(tastes.len() as f32 + 0.5) * ROW_HEIGHT - 8.0,
+                    0.1,
+                ),
+                ..default()
+            });
+        });
+}
+
+pub fn despawn_tooltip(mut commands: Commands, tooltip_query: Query<Entity, With<Tooltip>>) {
+    for ent in &tooltip_query {
+        commands.entity(ent).despawn_recursive();
+    }
+}
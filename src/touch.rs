@@ -0,0 +1,140 @@
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+
+use crate::replay::Playback;
+use crate::{
+    drop_zone, screen_to_world, shelved_food_at, waiting_at, Food, FoodState, Hand, HandAction,
+    MouthPoint, Pan, Shelf, Waiting,
+};
+
+#[derive(Default)]
+pub struct TouchDrag {
+    pub touch: Option<u64>,
+    ghost: Option<Entity>,
+}
+
+#[derive(Component)]
+pub struct DragGhost;
+
+fn touch_to_world(
+    touch: &Touch,
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Vec2 {
+    let mut screen = touch.position();
+    if !cfg!(any(target_os = "android", target_os = "ios")) {
+        screen.y = window.height() - screen.y;
+    }
+    screen_to_world(screen, window, camera, camera_transform)
+}
+
+pub fn touch_hand(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut drag: ResMut<TouchDrag>,
+    hand: Res<Hand>,
+    food_query: Query<(&Food, &Transform)>,
+    waiting_query: Query<(&Waiting, &Transform)>,
+    sprite_query: Query<(&Food, &TextureAtlasSprite, &Handle<TextureAtlas>)>,
+    shelves: Query<&Shelf>,
+    pan_query: Query<&Transform, (With<Pan>, Without<DragGhost>)>,
+    mouth_query: Query<&Transform, (With<MouthPoint>, Without<DragGhost>)>,
+    mut ghost_query: Query<&mut Transform, (With<DragGhost>, Without<Food>)>,
+    playback: Option<Res<Playback>>,
+    mut hand_action: EventWriter<HandAction>,
+) {
+    if playback.is_some() {
+        return;
+    }
+    let (camera, camera_transform) = match camera_query.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    if drag.touch.is_none() && hand.holding.is_none() {
+        for touch in touches.iter_just_pressed() {
+            let world = touch_to_world(touch, window, camera, camera_transform);
+            let food = match shelved_food_at(world, &food_query) {
+                Some(food) => food,
+                None => {
+                    if let Some(customer) = waiting_at(world, &waiting_query) {
+                        hand_action.send(HandAction::Select { customer });
+                    }
+                    continue;
+                }
+            };
+            let shelf = shelves.get(food.shelf).unwrap();
+            hand_action.send(HandAction::Take { shelf: shelf.index });
+            let (_, sprite, atlas) = sprite_query
+                .iter()
+                .find(|(other, _, _)| {
+                    other.state == FoodState::Shelved && other.shelf == food.shelf
+                })
+                .unwrap();
+            let ghost = commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite {
+                        index: sprite.index,
+                        color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+                        ..default()
+                    },
+                    texture_atlas: atlas.clone(),
+                    transform: Transform::from_translation(
+                        (world + Vec2::new(0.0, 64.0)).extend(9.0),
+                    ),
+                    ..default()
+                })
+                .insert(DragGhost)
+                .id();
+            drag.touch = Some(touch.id());
+            drag.ghost = Some(ghost);
+            break;
+        }
+    }
+
+    let id = match drag.touch {
+        Some(id) => id,
+        None => return,
+    };
+    if let Some(touch) = touches.get_pressed(id) {
+        let world = touch_to_world(touch, window, camera, camera_transform);
+        if let Some(ghost) = drag.ghost {
+            if let Ok(mut tr) = ghost_query.get_mut(ghost) {
+                tr.translation = (world + Vec2::new(0.0, 64.0)).extend(9.0);
+            }
+        }
+        return;
+    }
+    if let Some(touch) = touches.iter_just_released().find(|touch| touch.id() == id) {
+        let world = touch_to_world(touch, window, camera, camera_transform);
+        let zone = drop_zone(world, mouth_query.single(), pan_query.single());
+        hand_action.send(match zone {
+            Some(zone) => HandAction::Drop { zone },
+            None => HandAction::Return,
+        });
+    } else {
+        hand_action.send(HandAction::Return);
+    }
+    if let Some(ghost) = drag.ghost.take() {
+        commands.entity(ghost).despawn();
+    }
+    drag.touch = None;
+}
+
+pub fn clear_drag(
+    mut commands: Commands,
+    mut drag: ResMut<TouchDrag>,
+    ghost_query: Query<Entity, With<DragGhost>>,
+) {
+    for ent in &ghost_query {
+        commands.entity(ent).despawn();
+    }
+    *drag = TouchDrag::default();
+}